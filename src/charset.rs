@@ -0,0 +1,158 @@
+//! The character encoding of names and values, before percent-encoding.
+//!
+//! Legacy encodings are only available with the `query_encoding` feature,
+//! without which every form is encoded as UTF-8.
+
+use std::borrow::Cow;
+use std::str;
+
+#[cfg(feature = "query_encoding")]
+use encoding::{DecoderTrap, EncoderTrap, EncodingRef};
+#[cfg(feature = "query_encoding")]
+use encoding::label::encoding_from_whatwg_label;
+#[cfg(feature = "query_encoding")]
+use std::fmt;
+
+/// A character encoding, UTF-8 unless a legacy one is given.
+#[cfg(feature = "query_encoding")]
+#[derive(Clone, Copy)]
+pub struct Charset(Option<EncodingRef>);
+
+/// A character encoding, always UTF-8.
+#[cfg(not(feature = "query_encoding"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Charset;
+
+#[cfg(feature = "query_encoding")]
+impl Charset {
+    pub fn utf8() -> Self {
+        Charset(None)
+    }
+
+    pub fn new(encoding: EncodingRef) -> Self {
+        if encoding.name() == "utf-8" {
+            Charset(None)
+        } else {
+            Charset(Some(encoding))
+        }
+    }
+
+    /// Returns the name of the encoding, as used in `_charset_` fields.
+    pub fn name(&self) -> &'static str {
+        self.0.map_or("utf-8", |encoding| {
+            encoding.whatwg_name().unwrap_or_else(|| encoding.name())
+        })
+    }
+
+    /// Returns the encoding named by the value of a `_charset_` field, if
+    /// any.
+    pub fn for_label(label: &[u8]) -> Option<Charset> {
+        str::from_utf8(label)
+            .ok()
+            .and_then(encoding_from_whatwg_label)
+            .map(Charset::new)
+    }
+
+    /// Checks that the given input can be decoded from this encoding, which
+    /// requires it to be ASCII unless the encoding is UTF-8, as it would
+    /// otherwise be ambiguous.
+    pub fn check(&self, input: &[u8]) -> Result<(), ()> {
+        if self.0.is_some() && !input.is_ascii() {
+            Err(())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Decodes a percent-decoded name or value, replacing malformed
+    /// sequences unless `strict` is set and the encoding is UTF-8.
+    pub fn decode<'a>(&self, input: Cow<'a, [u8]>, strict: bool)
+                      -> Result<Cow<'a, str>, str::Utf8Error> {
+        match self.0 {
+            // Never fails when replacing malformed sequences.
+            Some(encoding) => {
+                Ok(Cow::Owned(encoding
+                    .decode(&input, DecoderTrap::Replace)
+                    .unwrap_or_default()))
+            },
+            None => decode_utf8(input, strict),
+        }
+    }
+
+    /// Encodes a name or value, replacing characters that cannot be
+    /// encoded with numeric character references as browsers do.
+    ///
+    /// UTF-16 encodings are replaced by UTF-8, as forms are never
+    /// submitted in them.
+    pub fn encode<'a>(&self, input: &'a str) -> Cow<'a, [u8]> {
+        match self.0 {
+            Some(encoding) if !self.name().starts_with("utf-16") => {
+                // Never fails when replacing with character references.
+                let bytes = encoding.encode(input, EncoderTrap::NcrEscape);
+                Cow::Owned(bytes.unwrap_or_default())
+            },
+            _ => Cow::Borrowed(input.as_bytes()),
+        }
+    }
+}
+
+#[cfg(feature = "query_encoding")]
+impl fmt::Debug for Charset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Charset").field(&self.name()).finish()
+    }
+}
+
+#[cfg(feature = "query_encoding")]
+impl PartialEq for Charset {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+#[cfg(feature = "query_encoding")]
+impl Eq for Charset {}
+
+#[cfg(not(feature = "query_encoding"))]
+impl Charset {
+    pub fn utf8() -> Self {
+        Charset
+    }
+
+    /// Returns `None`, as only UTF-8 is supported.
+    pub fn for_label(_label: &[u8]) -> Option<Charset> {
+        None
+    }
+
+    pub fn check(&self, _input: &[u8]) -> Result<(), ()> {
+        Ok(())
+    }
+
+    pub fn decode<'a>(&self, input: Cow<'a, [u8]>, strict: bool)
+                      -> Result<Cow<'a, str>, str::Utf8Error> {
+        decode_utf8(input, strict)
+    }
+
+    pub fn encode<'a>(&self, input: &'a str) -> Cow<'a, [u8]> {
+        Cow::Borrowed(input.as_bytes())
+    }
+}
+
+/// Decodes UTF-8, replacing malformed sequences unless `strict` is set.
+fn decode_utf8<'a>(input: Cow<'a, [u8]>, strict: bool)
+                   -> Result<Cow<'a, str>, str::Utf8Error> {
+    match input {
+        Cow::Borrowed(bytes) if strict => {
+            str::from_utf8(bytes).map(Cow::Borrowed)
+        },
+        Cow::Borrowed(bytes) => Ok(String::from_utf8_lossy(bytes)),
+        Cow::Owned(bytes) => match String::from_utf8(bytes) {
+            Ok(string) => Ok(Cow::Owned(string)),
+            Err(err) if strict => Err(err.utf8_error()),
+            Err(err) => {
+                Ok(Cow::Owned(
+                    String::from_utf8_lossy(err.as_bytes()).into_owned()))
+            },
+        },
+    }
+}
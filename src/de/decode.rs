@@ -0,0 +1,164 @@
+use charset::Charset;
+use de::{Config, Decoding, Error};
+use std::borrow::Cow;
+
+/// A decoded key and value.
+type Pair<'a> = (Cow<'a, str>, Cow<'a, str>);
+
+/// The pairs of a `&[u8]`, decoded according to the configured policy.
+pub struct BytePairs<'a> {
+    input: &'a [u8],
+    offset: usize,
+    config: Config,
+    charset: Charset,
+    error: Option<Error>,
+}
+
+impl<'a> BytePairs<'a> {
+    pub fn new(input: &'a [u8], config: Config) -> Self {
+        let detected = if config.detect_charset {
+            detect_charset(input, config)
+        } else {
+            None
+        };
+        let charset = detected.unwrap_or(config.charset);
+        match charset.check(input) {
+            Ok(()) => {
+                BytePairs {
+                    input,
+                    offset: 0,
+                    config,
+                    charset,
+                    error: None,
+                }
+            },
+            Err(()) => {
+                BytePairs {
+                    input: &[],
+                    offset: 0,
+                    config,
+                    charset,
+                    error: Some(Error::NonAscii { key: None }),
+                }
+            },
+        }
+    }
+}
+
+impl<'a> Iterator for BytePairs<'a> {
+    type Item = Result<Pair<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            return Some(Err(err));
+        }
+        while !self.input.is_empty() {
+            let input = self.input;
+            let offset = self.offset;
+            let separators = self.config.pair_separators;
+            let end = input.iter().position(|b| separators.contains(b));
+            let (pair, rest) = match end {
+                Some(end) => (&input[..end], &input[end + 1..]),
+                None => (input, &input[input.len()..]),
+            };
+            self.input = rest;
+            self.offset += input.len() - rest.len();
+            match decode_pair(pair, offset, self.config, self.charset) {
+                Ok(Some(pair)) => return Some(Ok(pair)),
+                Ok(None) => {},
+                Err(err) => {
+                    self.input = &[];
+                    return Some(Err(err));
+                },
+            }
+        }
+        None
+    }
+}
+
+/// Returns the encoding named by the first `_charset_` field of the input
+/// with a known one, if any.
+fn detect_charset(input: &[u8], config: Config) -> Option<Charset> {
+    input
+        .split(|b| config.pair_separators.contains(b))
+        .filter_map(|pair| pair.strip_prefix(&b"_charset_"[..]))
+        .filter_map(|rest| rest.split_first())
+        .filter(|&(&separator, _)| separator == config.key_value_separator)
+        .find_map(|(_, label)| Charset::for_label(label))
+}
+
+/// Decodes a pair found at the given byte offset of the input, returning
+/// `None` if it is empty.
+pub fn decode_pair<'a>(pair: &'a [u8], offset: usize, config: Config,
+                       charset: Charset)
+                       -> Result<Option<Pair<'a>>, Error> {
+    if pair.is_empty() {
+        return Ok(None);
+    }
+    let split = pair.iter().position(|&b| b == config.key_value_separator);
+    let (key, value, value_offset) = match split {
+        Some(end) => (&pair[..end], &pair[end + 1..], end + 1),
+        None => (pair, &pair[pair.len()..], pair.len()),
+    };
+    let key = decode(key, offset, config, charset)?;
+    let value = decode(value, offset + value_offset, config, charset)
+        .map_err(|err| err.at(&key))?;
+    Ok(Some((key, value)))
+}
+
+fn decode<'a>(input: &'a [u8], offset: usize, config: Config,
+              charset: Charset)
+              -> Result<Cow<'a, str>, Error> {
+    let bytes = match config.decoding {
+        Decoding::Raw => Cow::Borrowed(input),
+        Decoding::Lenient | Decoding::Strict => {
+            percent_decode(input, offset, config)?
+        },
+    };
+    let strict = config.decoding == Decoding::Strict;
+    charset.decode(bytes, strict).map_err(|error| {
+        Error::Utf8 { key: None, error }
+    })
+}
+
+/// Percent-decodes the given input, replacing `+` with spaces if
+/// configured, and borrowing it when there is nothing to decode.
+fn percent_decode<'a>(input: &'a [u8], offset: usize, config: Config)
+                      -> Result<Cow<'a, [u8]>, Error> {
+    let plus = config.plus_as_space;
+    if !input.iter().any(|&b| b == b'%' || (plus && b == b'+')) {
+        return Ok(Cow::Borrowed(input));
+    }
+    let mut output = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'+' if plus => output.push(b' '),
+            b'%' => {
+                let hi = input.get(i + 1).and_then(hex);
+                let lo = input.get(i + 2).and_then(hex);
+                match (hi, lo) {
+                    (Some(hi), Some(lo)) => {
+                        output.push(hi << 4 | lo);
+                        i += 3;
+                        continue;
+                    },
+                    _ if config.decoding == Decoding::Strict => {
+                        return Err(Error::InvalidEscape {
+                            key: None,
+                            offset: offset + i,
+                        });
+                    },
+                    _ => output.push(b'%'),
+                }
+            },
+            byte => output.push(byte),
+        }
+        i += 1;
+    }
+    Ok(Cow::Owned(output))
+}
+
+fn hex(byte: &u8) -> Option<u8> {
+    (*byte as char).to_digit(16).map(|digit| digit as u8)
+}
@@ -0,0 +1,653 @@
+use de::{Config, DuplicateKeys, EmptyValue, Error, Nesting};
+use de::part::Part;
+use serde::de;
+use serde::de::value::ValueDeserializer;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::mem;
+use std::vec;
+
+/// The values of all the pairs sharing a given key, in order of appearance.
+///
+/// Values given with an explicit index or field name, such as `ids[0]=1`
+/// or `user[name]=x`, are stored as children of the group.
+pub struct Group<'a> {
+    key: Cow<'a, str>,
+    path: Cow<'a, str>,
+    values: Vec<Cow<'a, str>>,
+    children: Groups<'a>,
+    /// Whether scalars receive the first value rather than the last one.
+    first: bool,
+    config: Config,
+}
+
+impl<'a> Group<'a> {
+    fn new(key: Cow<'a, str>, path: Cow<'a, str>, config: Config) -> Self {
+        Group {
+            key,
+            path,
+            values: vec![],
+            children: Groups::default(),
+            first: false,
+            config,
+        }
+    }
+
+    /// Returns the value of the group used for scalars, the last one
+    /// unless the first one was asked for.
+    fn part(&mut self) -> Result<Part<'a>, Error> {
+        let value = if self.first && !self.values.is_empty() {
+            Some(self.values.remove(0))
+        } else {
+            self.values.pop()
+        };
+        match value {
+            Some(value) => {
+                Ok(Part::new(self.path.clone(), value, self.config))
+            },
+            None => {
+                Err(Error::EndOfStream { key: Some(self.path.to_string()) })
+            },
+        }
+    }
+
+    fn index(&self) -> Result<usize, Error> {
+        self.key.parse().map_err(|_| {
+            Error::invalid_value_for(
+                &self.path, &self.key, "not a valid index".into())
+        })
+    }
+}
+
+/// Groups of values, in order of first appearance of their key.
+#[derive(Default)]
+pub struct Groups<'a> {
+    groups: Vec<Group<'a>>,
+    indices: HashMap<Cow<'a, str>, usize>,
+}
+
+impl<'a> Groups<'a> {
+    /// Groups the given pairs by key.
+    pub fn new<I>(pairs: I, config: Config) -> Result<Self, Error>
+        where I: IntoIterator<Item = Result<(Cow<'a, str>, Cow<'a, str>),
+                                            Error>>,
+    {
+        let mut groups = Groups::default();
+        for pair in pairs {
+            let (key, value) = pair?;
+            groups.insert(key, value, config)?;
+        }
+        Ok(groups)
+    }
+
+    /// Adds a value to the group of its key, applying the configured
+    /// `DuplicateKeys` policy unless the key ends with `[]`, after checking
+    /// the key against the depth and index limits.
+    fn insert(&mut self, key: Cow<'a, str>, value: Cow<'a, str>,
+              config: Config)
+              -> Result<(), Error> {
+        let mut append = false;
+        let group = match split_key(&key, config) {
+            Some((name, segments)) => {
+                let limits = config.limits;
+                let depth = segments
+                    .iter()
+                    .filter(|segment| !segment.name.is_empty())
+                    .count();
+                limits.check_depth(&key, depth)?;
+                for segment in &segments {
+                    limits.check_index(&key, &segment.name)?;
+                }
+                let mut group = self.group(name.clone(), name, config);
+                for segment in segments {
+                    if segment.name.is_empty() {
+                        append = true;
+                        break;
+                    }
+                    let path = slice(&key, 0, segment.end);
+                    group = group.children.group(segment.name, path, config);
+                }
+                group
+            },
+            None => self.group(key.clone(), key, config),
+        };
+        if !append && !group.values.is_empty() {
+            match config.duplicate_keys {
+                DuplicateKeys::Collect => {},
+                DuplicateKeys::First => return Ok(()),
+                DuplicateKeys::Last => group.values.clear(),
+                DuplicateKeys::Error => {
+                    return Err(Error::DuplicateKey {
+                        key: group.path.to_string(),
+                    });
+                },
+            }
+        }
+        group.values.push(value);
+        Ok(())
+    }
+
+    /// Returns the group for the given key, creating it if needed.
+    fn group(&mut self, key: Cow<'a, str>, path: Cow<'a, str>,
+             config: Config)
+             -> &mut Group<'a> {
+        let index = match self.indices.get(&key) {
+            Some(&index) => index,
+            None => {
+                self.indices.insert(key.clone(), self.groups.len());
+                self.groups.push(Group::new(key, path, config));
+                self.groups.len() - 1
+            },
+        };
+        &mut self.groups[index]
+    }
+
+    /// Removes the group for the given key, if any.
+    pub fn remove(&mut self, key: &str) -> Option<Group<'a>> {
+        let index = self.indices.remove(key)?;
+        for other in self.indices.values_mut() {
+            if *other > index {
+                *other -= 1;
+            }
+        }
+        Some(self.groups.remove(index))
+    }
+
+    /// Returns a map visitor over the groups, whose keys are nested in the
+    /// given path, empty at the top level.
+    pub fn into_map(self, path: Cow<'a, str>, nesting: Nesting)
+                    -> GroupedMap<'a> {
+        GroupedMap {
+            groups: self.groups.into_iter(),
+            group: None,
+            path,
+            nesting,
+        }
+    }
+}
+
+/// A nested segment of a key, along with the offset of its end in the key.
+struct Segment<'a> {
+    name: Cow<'a, str>,
+    end: usize,
+}
+
+/// Splits a key into its name and the segments recognized by the given
+/// configuration, if any.
+///
+/// With `Nesting::Brackets`, `user[address][city]` is split into `user`,
+/// `address` and `city`, and only the last segment may be empty. With
+/// `Nesting::Dots`, `user.address.city` is split the same way and no
+/// segment may be empty. With array brackets, a single trailing `[]` or
+/// `[N]` is recognized too, after dotted segments if any.
+fn split_key<'a>(key: &Cow<'a, str>, config: Config)
+                 -> Option<(Cow<'a, str>, Vec<Segment<'a>>)> {
+    let (name, segments) = match config.nesting {
+        Nesting::Disabled if !config.array_brackets => return None,
+        Nesting::Disabled | Nesting::Brackets => split_brackets(key)?,
+        Nesting::Dots if config.array_brackets => {
+            split_dots_and_brackets(key)?
+        },
+        Nesting::Dots => split_dots(key)?,
+    };
+    let last = segments.len() - 1;
+    let valid = match config.nesting {
+        Nesting::Disabled => last == 0 && is_index(&segments[0].name),
+        Nesting::Brackets => {
+            segments[..last].iter().all(|segment| !segment.name.is_empty())
+        },
+        Nesting::Dots => {
+            let appended = config.array_brackets && key.ends_with("[]");
+            segments[..last].iter().all(|segment| !segment.name.is_empty()) &&
+                (appended || !segments[last].name.is_empty())
+        },
+    };
+    if valid {
+        Some((name, segments))
+    } else {
+        None
+    }
+}
+
+/// Returns whether a bracketed segment is empty or an index.
+fn is_index(segment: &str) -> bool {
+    segment.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn split_dots<'a>(key: &Cow<'a, str>)
+                  -> Option<(Cow<'a, str>, Vec<Segment<'a>>)> {
+    let mut start = match key.find('.') {
+        Some(0) | None => return None,
+        Some(start) => start,
+    };
+    let name = slice(key, 0, start);
+    let mut segments = vec![];
+    while start < key.len() {
+        let end = key[start + 1..]
+            .find('.')
+            .map_or(key.len(), |end| start + 1 + end);
+        segments.push(Segment {
+            name: slice(key, start + 1, end),
+            end,
+        });
+        start = end;
+    }
+    Some((name, segments))
+}
+
+/// Splits a dotted key whose last segment may end with `[]` or `[N]`.
+fn split_dots_and_brackets<'a>(key: &Cow<'a, str>)
+                               -> Option<(Cow<'a, str>, Vec<Segment<'a>>)> {
+    let start = match key.rfind('[') {
+        Some(start) if start > 0 && key.ends_with(']') => start,
+        _ => return split_dots(key),
+    };
+    let index = slice(key, start + 1, key.len() - 1);
+    if !is_index(&index) {
+        return split_dots(key);
+    }
+    let dotted = slice(key, 0, start);
+    let (name, mut segments) = match split_dots(&dotted) {
+        Some((name, segments)) => (name, segments),
+        None if dotted.contains('.') => return None,
+        None => (dotted, vec![]),
+    };
+    segments.push(Segment { name: index, end: key.len() });
+    Some((name, segments))
+}
+
+fn split_brackets<'a>(key: &Cow<'a, str>)
+                      -> Option<(Cow<'a, str>, Vec<Segment<'a>>)> {
+    let mut start = match key.find('[') {
+        Some(0) | None => return None,
+        Some(start) => start,
+    };
+    let name = slice(key, 0, start);
+    let mut segments = vec![];
+    while start < key.len() {
+        if key.as_bytes()[start] != b'[' {
+            return None;
+        }
+        let end = start + key[start..].find(']')?;
+        segments.push(Segment {
+            name: slice(key, start + 1, end),
+            end: end + 1,
+        });
+        start = end + 1;
+    }
+    Some((name, segments))
+}
+
+fn slice<'a>(input: &Cow<'a, str>, start: usize, end: usize) -> Cow<'a, str> {
+    match *input {
+        Cow::Borrowed(input) => Cow::Borrowed(&input[start..end]),
+        Cow::Owned(ref input) => Cow::Owned(input[start..end].to_owned()),
+    }
+}
+
+/// A map visitor over groups of values.
+pub struct GroupedMap<'a> {
+    groups: vec::IntoIter<Group<'a>>,
+    group: Option<Group<'a>>,
+    path: Cow<'a, str>,
+    nesting: Nesting,
+}
+
+impl<'a> de::MapVisitor for GroupedMap<'a> {
+    type Error = Error;
+
+    fn visit_key<K>(&mut self) -> Result<Option<K>, Error>
+        where K: de::Deserialize,
+    {
+        match self.groups.next() {
+            Some(group) => {
+                let mut key =
+                    ValueDeserializer::<Error>::into_deserializer(
+                        group.key.clone());
+                let path = group.path.clone();
+                self.group = Some(group);
+                de::Deserialize::deserialize(&mut key)
+                    .map(Some)
+                    .map_err(|err| match err {
+                        Error::UnknownField { .. } => {
+                            Error::UnknownField { key: path.into_owned() }
+                        },
+                        err => err.at(&path),
+                    })
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn visit_value<V>(&mut self) -> Result<V, Error>
+        where V: de::Deserialize,
+    {
+        match self.group.take() {
+            Some(mut group) => {
+                de::Deserialize::deserialize(&mut group)
+                    .map_err(|err| err.at(&group.path))
+            },
+            None => Err(de::Error::end_of_stream()),
+        }
+    }
+
+    fn end(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.groups.size_hint()
+    }
+
+    /// Deserializes absent optional fields as `None`.
+    fn missing_field<V>(&mut self, field: &'static str) -> Result<V, Error>
+        where V: de::Deserialize,
+    {
+        let key = match (&*self.path, self.nesting) {
+            ("", _) => field.to_owned(),
+            (path, Nesting::Dots) => format!("{}.{}", path, field),
+            (path, _) => format!("{}[{}]", path, field),
+        };
+        de::Deserialize::deserialize(&mut MissingField(key))
+    }
+}
+
+/// Deserializes the given values of a key, as if they were grouped from
+/// the pairs of a map, except that scalars receive the first value.
+pub fn from_values<'a, T>(key: &'a str, values: Vec<&'a str>, config: Config)
+                          -> Result<T, Error>
+    where T: de::Deserialize,
+{
+    if values.is_empty() {
+        return de::Deserialize::deserialize(&mut MissingField(key.into()));
+    }
+    let mut group = Group::new(key.into(), key.into(), config);
+    group.values = values.into_iter().map(Cow::Borrowed).collect();
+    group.first = true;
+    de::Deserialize::deserialize(&mut group).map_err(|err| err.at(key))
+}
+
+/// A deserializer for absent fields, which only supports options.
+struct MissingField(String);
+
+impl de::Deserializer for MissingField {
+    type Error = Error;
+
+    fn deserialize<V>(&mut self, _visitor: V) -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        Err(Error::MissingField { key: self.0.clone() })
+    }
+
+    fn deserialize_option<V>(&mut self, mut visitor: V)
+                             -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        visitor.visit_none()
+    }
+
+    forward_to_deserialize! {
+        bool usize u8 u16 u32 u64 isize i8 i16 i32 i64 f32 f64 char str
+        string unit seq seq_fixed_size bytes map unit_struct newtype_struct
+        tuple_struct struct struct_field tuple enum ignored_any
+    }
+}
+
+/// A sequence visitor over the values of a group, followed by its indexed
+/// children in order.
+struct GroupSeq<'a> {
+    path: Cow<'a, str>,
+    values: vec::IntoIter<Cow<'a, str>>,
+    children: vec::IntoIter<(usize, Group<'a>)>,
+    config: Config,
+}
+
+impl<'a> de::SeqVisitor for GroupSeq<'a> {
+    type Error = Error;
+
+    fn visit<T>(&mut self) -> Result<Option<T>, Error>
+        where T: de::Deserialize,
+    {
+        if let Some(value) = self.values.next() {
+            let mut part = Part::new(self.path.clone(), value, self.config);
+            de::Deserialize::deserialize(&mut part)
+                .map(Some)
+                .map_err(|err| err.at(&self.path))
+        } else if let Some((_, mut child)) = self.children.next() {
+            de::Deserialize::deserialize(&mut child)
+                .map(Some)
+                .map_err(|err| err.at(&child.path))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn end(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.values.len() + self.children.len();
+        (len, Some(len))
+    }
+}
+
+macro_rules! forward_to_part {
+    ($($method:ident($($arg:ident: $ty:ty),*),)*) => {
+        $(
+            fn $method<V>(&mut self, $($arg: $ty,)* visitor: V)
+                          -> Result<V::Value, Error>
+                where V: de::Visitor,
+            {
+                de::Deserializer::$method(
+                    &mut self.part()?, $($arg,)* visitor)
+            }
+        )*
+    }
+}
+
+impl<'a> de::Deserializer for Group<'a> {
+    type Error = Error;
+
+    fn deserialize<V>(&mut self, visitor: V) -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        if self.values.is_empty() {
+            return self.deserialize_map(visitor);
+        }
+        if self.config.infer_types && self.values.len() > 1 {
+            return self.deserialize_seq(visitor);
+        }
+        de::Deserializer::deserialize(&mut self.part()?, visitor)
+    }
+
+    /// Deserializes the children of the group as a map, unless the group
+    /// only has values.
+    fn deserialize_map<V>(&mut self, mut visitor: V)
+                          -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        if self.children.groups.is_empty() && !self.values.is_empty() {
+            return de::Deserializer::deserialize_map(
+                &mut self.part()?, visitor);
+        }
+        let path = self.path.clone();
+        visitor.visit_map(
+            mem::take(&mut self.children).into_map(path, self.config.nesting))
+    }
+
+    fn deserialize_struct<V>(
+            &mut self,
+            _name: &'static str,
+            _fields: &'static [&'static str],
+            visitor: V)
+            -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    /// Deserializes a group whose values are all empty according to the
+    /// configured `EmptyValue` policy, and any other group as `Some`, so
+    /// that it can still hold a sequence or a tuple.
+    fn deserialize_option<V>(&mut self, mut visitor: V)
+                             -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        let empty = self.children.groups.is_empty() &&
+            !self.values.is_empty() &&
+            self.values.iter().all(|value| value.is_empty());
+        match self.config.empty_value {
+            EmptyValue::None if empty => visitor.visit_none(),
+            EmptyValue::Error if empty => {
+                Err(Error::invalid_value_for(
+                    &self.path, "", "empty value".into()))
+            },
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+            &mut self, _name: &'static str, mut visitor: V)
+            -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V>(&mut self, mut visitor: V)
+                          -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        let mut children = mem::take(&mut self.children)
+            .groups
+            .into_iter()
+            .map(|child| child.index().map(|index| (index, child)))
+            .collect::<Result<Vec<_>, _>>()?;
+        children.sort_by_key(|&(index, _)| index);
+        visitor.visit_seq(GroupSeq {
+            path: self.path.clone(),
+            values: mem::take(&mut self.values).into_iter(),
+            children: children.into_iter(),
+            config: self.config,
+        })
+    }
+
+    fn deserialize_seq_fixed_size<V>(&mut self, _len: usize, visitor: V)
+                                     -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple<V>(&mut self, _len: usize, visitor: V)
+                            -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V>(
+            &mut self, _name: &'static str, _len: usize, visitor: V)
+            -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    /// Drops the values and children of the group, which may have no value
+    /// of its own.
+    fn deserialize_ignored_any<V>(&mut self, mut visitor: V)
+                                  -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        self.values.clear();
+        self.children = Groups::default();
+        visitor.visit_unit()
+    }
+
+    fn deserialize_enum<V>(
+            &mut self,
+            name: &'static str,
+            variants: &'static [&'static str],
+            visitor: V)
+            -> Result<V::Value, Error>
+        where V: de::EnumVisitor,
+    {
+        de::Deserializer::deserialize_enum(
+            &mut self.part()?, name, variants, visitor)
+    }
+
+    forward_to_part! {
+        deserialize_bool(),
+        deserialize_usize(),
+        deserialize_u8(),
+        deserialize_u16(),
+        deserialize_u32(),
+        deserialize_u64(),
+        deserialize_isize(),
+        deserialize_i8(),
+        deserialize_i16(),
+        deserialize_i32(),
+        deserialize_i64(),
+        deserialize_f32(),
+        deserialize_f64(),
+        deserialize_char(),
+        deserialize_str(),
+        deserialize_string(),
+        deserialize_unit(),
+        deserialize_bytes(),
+        deserialize_unit_struct(name: &'static str),
+        deserialize_struct_field(),
+    }
+}
+
+/// The variant of a top-level enum, named by the group of its tag key and
+/// holding the remaining groups as its contents.
+pub struct Variant<'a> {
+    tag: Group<'a>,
+    contents: Group<'a>,
+}
+
+impl<'a> Variant<'a> {
+    pub fn new(tag: Group<'a>, groups: Groups<'a>, config: Config) -> Self {
+        let mut contents = Group::new("".into(), "".into(), config);
+        contents.children = groups;
+        Variant { tag, contents }
+    }
+}
+
+impl<'a> de::VariantVisitor for Variant<'a> {
+    type Error = Error;
+
+    fn visit_variant<V>(&mut self) -> Result<V, Error>
+        where V: de::Deserialize,
+    {
+        de::Deserialize::deserialize(&mut self.tag.part()?)
+    }
+
+    fn visit_unit(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn visit_newtype<T>(&mut self) -> Result<T, Error>
+        where T: de::Deserialize,
+    {
+        de::Deserialize::deserialize(&mut self.contents)
+    }
+
+    fn visit_tuple<V>(&mut self, _len: usize, _visitor: V)
+                      -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        Err(de::Error::invalid_type(de::Type::TupleVariant))
+    }
+
+    fn visit_struct<V>(
+            &mut self, _fields: &'static [&'static str], visitor: V)
+            -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        de::Deserializer::deserialize_map(&mut self.contents, visitor)
+    }
+}
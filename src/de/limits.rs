@@ -0,0 +1,193 @@
+use de::Error;
+use std::borrow::Cow;
+
+/// Bounds on the input accepted by the deserializer, all unlimited by
+/// default except for the depth of nested keys, at most 32.
+///
+/// Going over a limit is reported as an `Error::LimitExceeded` naming the
+/// limit, as soon as the offending pair is read.
+///
+/// ```
+/// use serde_urlencoded::de::{Config, Error, Limits};
+/// use std::collections::HashMap;
+///
+/// let config = Config::new().limits(Limits::new().max_pairs(2));
+///
+/// assert_eq!(
+///     config.from_str::<HashMap<String, String>>("a=1&b=2&c=3"),
+///     Err(Error::LimitExceeded { key: None, limit: "pairs", max: 2 }));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    max_pairs: Option<usize>,
+    max_key_len: Option<usize>,
+    max_value_len: Option<usize>,
+    max_total_len: Option<usize>,
+    max_depth: Option<usize>,
+    max_index: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_pairs: None,
+            max_key_len: None,
+            max_value_len: None,
+            max_total_len: None,
+            max_depth: Some(32),
+            max_index: None,
+        }
+    }
+}
+
+impl Limits {
+    /// Returns the default limits, only bounding the depth of nested keys.
+    pub fn new() -> Self {
+        Limits::default()
+    }
+
+    /// Sets the maximum number of pairs.
+    pub fn max_pairs(mut self, max: usize) -> Self {
+        self.max_pairs = Some(max);
+        self
+    }
+
+    /// Sets the maximum length of a decoded key, in bytes.
+    pub fn max_key_len(mut self, max: usize) -> Self {
+        self.max_key_len = Some(max);
+        self
+    }
+
+    /// Sets the maximum length of a decoded value, in bytes.
+    pub fn max_value_len(mut self, max: usize) -> Self {
+        self.max_value_len = Some(max);
+        self
+    }
+
+    /// Sets the maximum length of all the decoded keys and values together,
+    /// in bytes.
+    pub fn max_total_len(mut self, max: usize) -> Self {
+        self.max_total_len = Some(max);
+        self
+    }
+
+    /// Sets the maximum number of nested segments in a key, such as the two
+    /// of `user[address][city]`, when a `Nesting` mode is configured, 32 by
+    /// default.
+    ///
+    /// Deeper keys would otherwise exhaust the stack when deserialized.
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = Some(max);
+        self
+    }
+
+    /// Sets the maximum index in keys such as `ids[3]`.
+    pub fn max_index(mut self, max: usize) -> Self {
+        self.max_index = Some(max);
+        self
+    }
+
+    /// Returns the length past which an encoded pair is bound to exceed
+    /// these limits once decoded, if any, since decoding at most divides
+    /// the length of a key or value by three.
+    pub(crate) fn max_encoded_pair_len(&self) -> Option<usize> {
+        let pair = match (self.max_key_len, self.max_value_len) {
+            (Some(key), Some(value)) => Some(key + value),
+            _ => None,
+        };
+        let max = match (pair, self.max_total_len) {
+            (Some(pair), Some(total)) => pair.min(total),
+            (pair, total) => pair.or(total)?,
+        };
+        Some(max.saturating_mul(3).saturating_add(1))
+    }
+
+    /// Checks the depth of a key split into the given number of segments.
+    pub(crate) fn check_depth(&self, key: &str, depth: usize)
+                              -> Result<(), Error> {
+        check(self.max_depth, depth, "depth", || Some(key.to_owned()))
+    }
+
+    /// Checks a key segment made of digits, as used for indices.
+    pub(crate) fn check_index(&self, key: &str, segment: &str)
+                              -> Result<(), Error> {
+        let max = match self.max_index {
+            Some(max) => max,
+            None => return Ok(()),
+        };
+        let digits = segment.bytes().all(|b| b.is_ascii_digit());
+        if segment.is_empty() || !digits {
+            return Ok(());
+        }
+        let index = segment.parse().unwrap_or(usize::MAX);
+        check(Some(max), index, "array index", || Some(key.to_owned()))
+    }
+}
+
+fn check<F>(max: Option<usize>, actual: usize, limit: &'static str, key: F)
+            -> Result<(), Error>
+    where F: FnOnce() -> Option<String>,
+{
+    match max {
+        Some(max) if actual > max => {
+            Err(Error::LimitExceeded { key: key(), limit, max })
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Checks the number and lengths of decoded pairs against the given
+/// limits, stopping at the first error.
+pub struct LimitedPairs<I> {
+    pairs: I,
+    limits: Limits,
+    count: usize,
+    total: usize,
+    failed: bool,
+}
+
+impl<I> LimitedPairs<I> {
+    pub fn new(pairs: I, limits: Limits) -> Self {
+        LimitedPairs {
+            pairs,
+            limits,
+            count: 0,
+            total: 0,
+            failed: false,
+        }
+    }
+
+    fn check(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        let limits = self.limits;
+        self.count += 1;
+        self.total = self.total
+            .saturating_add(key.len())
+            .saturating_add(value.len());
+        check(limits.max_pairs, self.count, "pairs", || None)?;
+        check(limits.max_key_len, key.len(), "key length", || None)?;
+        check(limits.max_value_len, value.len(), "value length", || {
+            Some(key.to_owned())
+        })?;
+        check(limits.max_total_len, self.total, "total length", || None)
+    }
+}
+
+impl<'a, I> Iterator for LimitedPairs<I>
+    where I: Iterator<Item = Result<(Cow<'a, str>, Cow<'a, str>), Error>>,
+{
+    type Item = Result<(Cow<'a, str>, Cow<'a, str>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let result = match self.pairs.next()? {
+            Ok((key, value)) => {
+                self.check(&key, &value).map(|()| (key, value))
+            },
+            Err(err) => Err(err),
+        };
+        self.failed = result.is_err();
+        Some(result)
+    }
+}
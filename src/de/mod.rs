@@ -0,0 +1,1081 @@
+//! Deserialization support for the `application/x-www-form-urlencoded` format.
+
+mod decode;
+mod group;
+mod limits;
+mod part;
+mod reader;
+
+use serde::de;
+use serde::de::value::MapDeserializer;
+use std::borrow::Cow;
+use std::error;
+use std::fmt;
+use std::io;
+use std::iter;
+use std::str;
+use std::sync::Arc;
+use url::form_urlencoded::Parse as UrlEncodedParse;
+
+use charset::Charset;
+use ser::is_separator;
+#[cfg(feature = "query_encoding")]
+use encoding::EncodingRef;
+
+use self::decode::BytePairs;
+use self::group::{Groups, Variant};
+use self::limits::LimitedPairs;
+use self::part::Part;
+use self::reader::ReaderPairs;
+
+pub use self::limits::Limits;
+pub use ser::Nesting;
+
+/// Deserializes a `application/x-wwww-url-encoded` value from a `&[u8]`.
+///
+/// ```
+/// let meal = vec![
+///     ("bread".to_owned(), "baguette".to_owned()),
+///     ("cheese".to_owned(), "comté".to_owned()),
+///     ("meat".to_owned(), "ham".to_owned()),
+///     ("fat".to_owned(), "butter".to_owned()),
+/// ];
+///
+/// assert_eq!(
+///     serde_urlencoded::from_bytes::<Vec<(String, String)>>(
+///         b"bread=baguette&cheese=comt%C3%A9&meat=ham&fat=butter"),
+///     Ok(meal));
+/// ```
+pub fn from_bytes<T: de::Deserialize>(input: &[u8]) -> Result<T, Error> {
+    Config::new().from_bytes(input)
+}
+
+/// Deserializes a `application/x-wwww-url-encoded` value from a `&str`.
+///
+/// ```
+/// let meal = vec![
+///     ("bread".to_owned(), "baguette".to_owned()),
+///     ("cheese".to_owned(), "comté".to_owned()),
+///     ("meat".to_owned(), "ham".to_owned()),
+///     ("fat".to_owned(), "butter".to_owned()),
+/// ];
+///
+/// assert_eq!(
+///     serde_urlencoded::from_str::<Vec<(String, String)>>(
+///         "bread=baguette&cheese=comt%C3%A9&meat=ham&fat=butter"),
+///     Ok(meal));
+/// ```
+pub fn from_str<T: de::Deserialize>(input: &str) -> Result<T, Error> {
+    from_bytes(input.as_bytes())
+}
+
+/// Deserializes a `application/x-wwww-url-encoded` value from a `&[u8]`
+/// whose names and values are in the given character encoding.
+///
+/// Non-ASCII bytes must be percent-encoded, as they would be ambiguous.
+///
+/// ```
+/// extern crate encoding;
+/// extern crate serde_urlencoded;
+///
+/// use encoding::all::WINDOWS_1252;
+///
+/// # fn main() {
+/// let meal = vec![("cheese".to_owned(), "comté".to_owned())];
+///
+/// assert_eq!(
+///     serde_urlencoded::from_bytes_with_encoding(
+///         b"cheese=comt%E9", WINDOWS_1252),
+///     Ok(meal));
+/// # }
+/// ```
+#[cfg(feature = "query_encoding")]
+pub fn from_bytes_with_encoding<T>(input: &[u8], encoding: EncodingRef)
+                                   -> Result<T, Error>
+    where T: de::Deserialize,
+{
+    Config::new().encoding(encoding).from_bytes(input)
+}
+
+/// Deserializes a `application/x-wwww-url-encoded` value from an `io::Read`.
+///
+/// The input is read in chunks and decoded one pair at a time.
+///
+/// ```
+/// let body = &b"bread=baguette&cheese=comt%C3%A9"[..];
+///
+/// assert_eq!(
+///     serde_urlencoded::from_reader::<_, Vec<(String, String)>>(body),
+///     Ok(vec![
+///         ("bread".to_owned(), "baguette".to_owned()),
+///         ("cheese".to_owned(), "comté".to_owned()),
+///     ]));
+/// ```
+pub fn from_reader<R, T>(reader: R) -> Result<T, Error>
+    where R: io::Read,
+          T: de::Deserialize,
+{
+    Config::new().from_reader(reader)
+}
+
+/// Deserializes a value from pairs that are already decoded.
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// let mut query = HashMap::new();
+/// query.insert("page".to_owned(), "2".to_owned());
+///
+/// let mut params = HashMap::new();
+/// params.insert("page".to_owned(), 2);
+///
+/// assert_eq!(serde_urlencoded::from_pairs(&query), Ok(params));
+/// ```
+pub fn from_pairs<'a, I, K, V, T>(pairs: I) -> Result<T, Error>
+    where I: IntoIterator<Item = (K, V)>,
+          I::IntoIter: 'a,
+          K: Into<Cow<'a, str>>,
+          V: Into<Cow<'a, str>>,
+          T: de::Deserialize,
+{
+    Config::new().from_pairs(pairs)
+}
+
+/// Options used when deserializing from `application/x-www-form-urlencoded`.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use serde_urlencoded::de::Config;
+///
+/// let mut ids = HashMap::new();
+/// ids.insert("ids".to_owned(), vec![1, 2, 3]);
+///
+/// assert_eq!(
+///     Config::new().array_brackets(true).from_str(
+///         "ids[1]=2&ids[0]=1&ids[2]=3"),
+///     Ok(ids));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    array_brackets: bool,
+    nesting: Nesting,
+    tag: &'static str,
+    empty_value: EmptyValue,
+    duplicate_keys: DuplicateKeys,
+    infer_types: bool,
+    limits: Limits,
+    charset: Charset,
+    detect_charset: bool,
+    decoding: Decoding,
+    plus_as_space: bool,
+    pair_separators: &'static [u8],
+    key_value_separator: u8,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            array_brackets: false,
+            nesting: Nesting::default(),
+            tag: "type",
+            empty_value: EmptyValue::default(),
+            duplicate_keys: DuplicateKeys::default(),
+            infer_types: false,
+            limits: Limits::default(),
+            charset: Charset::utf8(),
+            detect_charset: false,
+            decoding: Decoding::default(),
+            plus_as_space: true,
+            pair_separators: b"&",
+            key_value_separator: b'=',
+        }
+    }
+}
+
+impl Config {
+    /// Returns the default configuration.
+    pub fn new() -> Self {
+        Config::default()
+    }
+
+    /// Sets whether `key[]` and `key[N]` keys are recognized as elements of
+    /// a sequence named `key`, disabled by default.
+    ///
+    /// Elements given with an index are ordered by index and follow the
+    /// ones given without one.
+    pub fn array_brackets(mut self, enabled: bool) -> Self {
+        self.array_brackets = enabled;
+        self
+    }
+
+    /// Sets how keys of nested maps and structs are recognized,
+    /// `Nesting::Disabled` by default.
+    ///
+    /// `Nesting::Brackets` also recognizes the keys of sequences, as
+    /// `array_brackets` does, so that `items[0][name]=x` is supported.
+    /// `Nesting::Dots` recognizes dotted keys such as `filter.owner.id=7`,
+    /// followed by a trailing `[]` or `[N]` if `array_brackets` is enabled.
+    ///
+    /// ```
+    /// use serde_urlencoded::de::{Config, Nesting};
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut address = BTreeMap::new();
+    /// address.insert("city".to_owned(), "Paris".to_owned());
+    /// let mut user = BTreeMap::new();
+    /// user.insert("address".to_owned(), address);
+    ///
+    /// assert_eq!(
+    ///     Config::new()
+    ///         .nesting(Nesting::Brackets)
+    ///         .from_str("address[city]=Paris"),
+    ///     Ok(user));
+    /// ```
+    pub fn nesting(mut self, nesting: Nesting) -> Self {
+        self.nesting = nesting;
+        self
+    }
+
+    /// Sets the key holding the variant name of top-level enums, `type` by
+    /// default.
+    ///
+    /// ```
+    /// use serde_urlencoded::de::Config;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut params = BTreeMap::new();
+    /// params.insert("number".to_owned(), 4242);
+    ///
+    /// assert_eq!(
+    ///     Config::new().tag("kind").from_str("kind=Ok&number=4242"),
+    ///     Ok(Ok::<_, ()>(params)));
+    /// ```
+    pub fn tag(mut self, tag: &'static str) -> Self {
+        self.tag = tag;
+        self
+    }
+
+    /// Sets how empty values, as in `key=` or a bare `key`, are
+    /// deserialized into options, `EmptyValue::None` by default.
+    ///
+    /// Absent keys are always deserialized as `None`.
+    ///
+    /// ```
+    /// use serde_urlencoded::de::{Config, EmptyValue};
+    ///
+    /// let params = vec![("middle".to_owned(), Some("".to_owned()))];
+    ///
+    /// assert_eq!(
+    ///     Config::new().empty_value(EmptyValue::Some).from_str("middle="),
+    ///     Ok(params));
+    /// ```
+    pub fn empty_value(mut self, empty_value: EmptyValue) -> Self {
+        self.empty_value = empty_value;
+        self
+    }
+
+    /// Sets how keys given more than once are handled when deserializing
+    /// maps and structs, `DuplicateKeys::Collect` by default.
+    ///
+    /// Keys ending with `[]` are always collected, as they explicitly
+    /// append to a sequence.
+    ///
+    /// ```
+    /// use serde_urlencoded::de::{Config, DuplicateKeys, Error};
+    /// use std::collections::HashMap;
+    ///
+    /// let config = Config::new().duplicate_keys(DuplicateKeys::Error);
+    ///
+    /// assert_eq!(
+    ///     config.from_str::<HashMap<String, String>>("role=user&role=admin"),
+    ///     Err(Error::DuplicateKey { key: "role".to_owned() }));
+    /// ```
+    pub fn duplicate_keys(mut self, duplicate_keys: DuplicateKeys) -> Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+
+    /// Sets whether values that look like booleans, integers or floats are
+    /// given as such to self-describing types, disabled by default.
+    ///
+    /// This lets untagged enums and dynamic types such as
+    /// `serde_json::Value` pick the right type, and makes repeated keys
+    /// sequences for them. Types requesting a string still receive the
+    /// value as written.
+    ///
+    /// ```
+    /// extern crate serde_json;
+    /// extern crate serde_urlencoded;
+    ///
+    /// use serde_json::Value;
+    /// use serde_urlencoded::de::Config;
+    ///
+    /// # fn main() {
+    /// let value: Value = Config::new()
+    ///     .infer_types(true)
+    ///     .from_str("page=2&draft=false&q=rust")
+    ///     .unwrap();
+    ///
+    /// assert_eq!(value.find("page"), Some(&Value::U64(2)));
+    /// assert_eq!(value.find("draft"), Some(&Value::Bool(false)));
+    /// assert_eq!(value.find("q"), Some(&Value::String("rust".to_owned())));
+    /// # }
+    /// ```
+    pub fn infer_types(mut self, enabled: bool) -> Self {
+        self.infer_types = enabled;
+        self
+    }
+
+    /// Sets the limits on the input, `Limits::default()` by default, which
+    /// only bounds the depth of nested keys.
+    ///
+    /// When reading from an `io::Read`, pairs are only buffered up to the
+    /// length allowed by the key and value length limits, or the total
+    /// length limit.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets how percent-encoded escapes in keys and values are decoded,
+    /// `Decoding::Lenient` by default.
+    ///
+    /// This only applies to inputs read from bytes, strings and readers.
+    ///
+    /// ```
+    /// use serde_urlencoded::de::{Config, Decoding, Error};
+    ///
+    /// assert_eq!(
+    ///     Config::new()
+    ///         .decoding(Decoding::Strict)
+    ///         .from_str::<Vec<(String, String)>>("a=1&discount=50%"),
+    ///     Err(Error::InvalidEscape {
+    ///         key: Some("discount".to_owned()),
+    ///         offset: 15,
+    ///     }));
+    /// ```
+    pub fn decoding(mut self, decoding: Decoding) -> Self {
+        self.decoding = decoding;
+        self
+    }
+
+    /// Sets whether `+` is decoded as a space, as in HTML forms, enabled by
+    /// default.
+    ///
+    /// Disabling it keeps a literal `+` in RFC 3986 query strings, where
+    /// spaces are written `%20`.
+    ///
+    /// ```
+    /// use serde_urlencoded::de::Config;
+    ///
+    /// let params = vec![("tz".to_owned(), "+02:00 CET".to_owned())];
+    ///
+    /// assert_eq!(
+    ///     Config::new().plus_as_space(false).from_str("tz=+02:00%20CET"),
+    ///     Ok(params));
+    /// ```
+    pub fn plus_as_space(mut self, enabled: bool) -> Self {
+        self.plus_as_space = enabled;
+        self
+    }
+
+    /// Sets the ASCII characters any of which separates pairs, `&` by
+    /// default.
+    ///
+    /// This only applies to inputs read from bytes, strings and readers.
+    ///
+    /// ```
+    /// use serde_urlencoded::de::Config;
+    ///
+    /// let params = vec![
+    ///     ("a".to_owned(), "1".to_owned()),
+    ///     ("b".to_owned(), "2".to_owned()),
+    ///     ("c".to_owned(), "3".to_owned()),
+    /// ];
+    ///
+    /// assert_eq!(
+    ///     Config::new().pair_separators(b"&;").from_str("a=1;b=2&c=3"),
+    ///     Ok(params));
+    /// ```
+    ///
+    /// Deserializing fails if the key/value separator is one of them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no separators, or if one of them is not ASCII
+    /// punctuation other than `%`, as it could not be told apart from keys,
+    /// values and escapes.
+    pub fn pair_separators(mut self, separators: &'static [u8]) -> Self {
+        assert!(!separators.is_empty(), "pair separators must not be empty");
+        assert!(separators.iter().all(|&b| is_separator(b)),
+                "pair separators must be ASCII punctuation other than `%`");
+        self.pair_separators = separators;
+        self
+    }
+
+    /// Sets the ASCII character separating keys from values, `=` by
+    /// default.
+    ///
+    /// This only applies to inputs read from bytes, strings and readers.
+    ///
+    /// Deserializing fails if it is also a pair separator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the separator is not ASCII punctuation other than `%`.
+    pub fn key_value_separator(mut self, separator: u8) -> Self {
+        assert!(is_separator(separator),
+                "key/value separator must be ASCII punctuation other than \
+                 `%`");
+        self.key_value_separator = separator;
+        self
+    }
+
+    /// Sets the character encoding of names and values, UTF-8 by default.
+    ///
+    /// Non-ASCII bytes must be percent-encoded when the encoding is not
+    /// UTF-8, as they would be ambiguous.
+    #[cfg(feature = "query_encoding")]
+    pub fn encoding(mut self, encoding: EncodingRef) -> Self {
+        self.charset = Charset::new(encoding);
+        self
+    }
+
+    /// Sets whether a `_charset_` field naming an encoding overrides the
+    /// configured one, disabled by default.
+    ///
+    /// The field is looked for before decoding, so it is only detected
+    /// when deserializing from bytes or strings, not from readers.
+    ///
+    /// ```
+    /// use serde_urlencoded::de::Config;
+    ///
+    /// let params = vec![
+    ///     ("name".to_owned(), "山田".to_owned()),
+    ///     ("_charset_".to_owned(), "shift_jis".to_owned()),
+    /// ];
+    ///
+    /// assert_eq!(
+    ///     Config::new()
+    ///         .detect_charset(true)
+    ///         .from_str("name=%8ER%93c&_charset_=shift_jis"),
+    ///     Ok(params));
+    /// ```
+    #[cfg(feature = "query_encoding")]
+    pub fn detect_charset(mut self, enabled: bool) -> Self {
+        self.detect_charset = enabled;
+        self
+    }
+
+    /// Deserializes a `application/x-wwww-url-encoded` value from a `&[u8]`
+    /// using this configuration.
+    pub fn from_bytes<T: de::Deserialize>(&self, input: &[u8])
+                                          -> Result<T, Error> {
+        T::deserialize(&mut Deserializer::from_bytes(input, *self))
+    }
+
+    /// Deserializes a `application/x-wwww-url-encoded` value from a `&str`
+    /// using this configuration.
+    pub fn from_str<T: de::Deserialize>(&self, input: &str)
+                                        -> Result<T, Error> {
+        self.from_bytes(input.as_bytes())
+    }
+
+    /// Deserializes a `application/x-wwww-url-encoded` value from an
+    /// `io::Read` using this configuration.
+    pub fn from_reader<R, T>(&self, reader: R) -> Result<T, Error>
+        where R: io::Read,
+              T: de::Deserialize,
+    {
+        T::deserialize(&mut Deserializer::from_reader(reader, *self))
+    }
+
+    /// Deserializes a value from pairs that are already decoded using this
+    /// configuration.
+    pub fn from_pairs<'a, I, K, V, T>(&self, pairs: I) -> Result<T, Error>
+        where I: IntoIterator<Item = (K, V)>,
+              I::IntoIter: 'a,
+              K: Into<Cow<'a, str>>,
+              V: Into<Cow<'a, str>>,
+              T: de::Deserialize,
+    {
+        T::deserialize(&mut Deserializer::from_pairs(pairs, *self))
+    }
+
+    /// Checks that the key/value separator is not a pair separator, which
+    /// cannot be done as they are set without depending on the order of
+    /// the calls.
+    fn check_separators(&self) -> Result<(), Error> {
+        if self.pair_separators.contains(&self.key_value_separator) {
+            return Err(Error::Custom {
+                key: None,
+                message: "pair and key/value separators must differ"
+                    .to_owned(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Deserializes the values of a single key, used by `Form::get_as`.
+pub(crate) fn from_values<'a, T>(key: &'a str, values: Vec<&'a str>)
+                                 -> Result<T, Error>
+    where T: de::Deserialize,
+{
+    group::from_values(key, values, Config::default())
+}
+
+/// How empty values are deserialized into options.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EmptyValue {
+    /// Empty values are `None`.
+    #[default]
+    None,
+    /// Empty values are deserialized as `Some` of the empty string, which
+    /// fails for types that cannot be parsed from it.
+    Some,
+    /// Empty values are an error.
+    Error,
+}
+
+/// How percent-encoded escapes in keys and values are decoded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Decoding {
+    /// Escapes are decoded, and malformed ones such as `%zz` or a trailing
+    /// `%` are kept as is, as browsers do.
+    #[default]
+    Lenient,
+    /// Escapes are decoded, and malformed ones are an error giving their
+    /// byte offset in the input, as are keys and values which are not
+    /// valid UTF-8 once decoded.
+    Strict,
+    /// Keys and values are kept as is, without decoding escapes nor
+    /// replacing `+` with spaces.
+    Raw,
+}
+
+/// How keys given more than once are handled when deserializing maps and
+/// structs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// All the values are kept: sequences receive all of them, in order,
+    /// and other values the last one.
+    #[default]
+    Collect,
+    /// Only the first value is kept.
+    First,
+    /// Only the last value is kept.
+    Last,
+    /// Duplicate keys are an error.
+    Error,
+}
+
+/// A deserializer for the `application/x-www-form-urlencoded` format.
+///
+/// * Supported top-level outputs are structs, maps and sequences of pairs,
+///   with or without a given length.
+///
+/// * Main `deserialize` methods defers to `deserialize_map`.
+///
+/// * Everything else but `deserialize_seq`, `deserialize_seq_fixed_size`
+///   and `deserialize_enum` defers to `deserialize`.
+///
+/// * Values are parsed into the primitive type requested by the visitor
+///   (integers, floats, booleans and chars), other values are given as
+///   strings, unless type inference is enabled.
+///
+/// * When deserializing maps and structs, values sharing a key are grouped
+///   together: values expecting a sequence receive all of them, in order,
+///   while other values receive the last one, unless another
+///   `DuplicateKeys` policy is configured.
+///
+/// * Nested maps and structs are supported when a `Nesting` mode is
+///   configured.
+///
+/// * Options are `None` when their key is absent, and empty values are
+///   handled according to the configured `EmptyValue` policy.
+///
+/// * Top-level enums are read from the variant name under the configured
+///   tag key, the remaining pairs being the contents of newtype and struct
+///   variants. Unit variant values are read from their names.
+pub struct Deserializer<'a> {
+    pairs: Pairs<'a>,
+    config: Config,
+}
+
+/// The decoded pairs given to a `Deserializer`.
+type Pairs<'a> =
+    Box<dyn Iterator<Item = Result<(Cow<'a, str>, Cow<'a, str>), Error>> + 'a>;
+
+impl<'a> Deserializer<'a> {
+    /// Returns a new `Deserializer`.
+    pub fn new(parser: UrlEncodedParse<'a>) -> Self {
+        Deserializer::with_config(parser, Config::default())
+    }
+
+    /// Returns a new `Deserializer` using the given configuration.
+    pub fn with_config(parser: UrlEncodedParse<'a>, config: Config) -> Self {
+        Deserializer::from_results(parser.map(Ok), config)
+    }
+
+    /// Returns a new `Deserializer` reading from a `&[u8]` using the given
+    /// configuration.
+    ///
+    /// Unlike `with_config`, this decodes keys and values according to the
+    /// configured `Decoding` policy and character encoding.
+    pub fn from_bytes(input: &'a [u8], config: Config) -> Self {
+        if let Err(err) = config.check_separators() {
+            return Deserializer::from_results(iter::once(Err(err)), config);
+        }
+        Deserializer::from_results(BytePairs::new(input, config), config)
+    }
+
+    /// Returns a new `Deserializer` reading from an `io::Read` using the
+    /// given configuration.
+    pub fn from_reader<R>(reader: R, config: Config) -> Self
+        where R: io::Read + 'a,
+    {
+        if let Err(err) = config.check_separators() {
+            return Deserializer::from_results(iter::once(Err(err)), config);
+        }
+        let pairs = ReaderPairs::new(reader, config).map(|pair| {
+            pair.map(|(key, value)| (Cow::Owned(key), Cow::Owned(value)))
+        });
+        Deserializer::from_results(pairs, config)
+    }
+
+    /// Returns a new `Deserializer` reading pairs that are already decoded
+    /// using the given configuration.
+    pub fn from_pairs<I, K, V>(pairs: I, config: Config) -> Self
+        where I: IntoIterator<Item = (K, V)>,
+              I::IntoIter: 'a,
+              K: Into<Cow<'a, str>>,
+              V: Into<Cow<'a, str>>,
+    {
+        let pairs = pairs
+            .into_iter()
+            .map(|(key, value)| Ok((key.into(), value.into())));
+        Deserializer::from_results(pairs, config)
+    }
+
+    fn from_results<I>(pairs: I, config: Config) -> Self
+        where I: Iterator<Item = Result<(Cow<'a, str>, Cow<'a, str>),
+                                        Error>> + 'a,
+    {
+        let pairs = LimitedPairs::new(pairs, config.limits);
+        Deserializer { pairs: Box::new(pairs), config }
+    }
+}
+
+impl<'a> de::Deserializer for Deserializer<'a>
+{
+    type Error = Error;
+
+    fn deserialize<V>(
+            &mut self, visitor: V)
+            -> Result<V::Value, Self::Error>
+        where V: de::Visitor,
+    {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V>(
+            &mut self, mut visitor: V)
+            -> Result<V::Value, Self::Error>
+        where V: de::Visitor,
+    {
+        visitor.visit_map(
+            Groups::new(self.pairs.by_ref(), self.config)?
+                .into_map("".into(), self.config.nesting))
+    }
+
+    fn deserialize_seq<V>(
+            &mut self, mut visitor: V)
+            -> Result<V::Value, Self::Error>
+        where V: de::Visitor,
+    {
+        let config = self.config;
+        let mut error = None;
+        let value = {
+            let pairs = self.pairs
+                .by_ref()
+                .scan(&mut error, |error, pair| match pair {
+                    Ok(pair) => Some(pair),
+                    Err(err) => {
+                        **error = Some(err);
+                        None
+                    },
+                })
+                .map(|(key, value)| {
+                    (key.clone(), Part::new(key, value, config))
+                });
+            visitor.visit_seq(MapDeserializer::unbounded(pairs))
+        };
+        match error {
+            Some(err) => Err(err),
+            None => value,
+        }
+    }
+
+    fn deserialize_seq_fixed_size<V>(
+            &mut self, _len: usize, visitor: V)
+            -> Result<V::Value, Self::Error>
+        where V: de::Visitor
+    {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V>(
+            &mut self,
+            _name: &'static str,
+            _variants: &'static [&'static str],
+            mut visitor: V)
+            -> Result<V::Value, Self::Error>
+        where V: de::EnumVisitor,
+    {
+        let mut groups = Groups::new(self.pairs.by_ref(), self.config)?;
+        match groups.remove(self.config.tag) {
+            Some(tag) => visitor.visit(Variant::new(tag, groups, self.config)),
+            None => {
+                Err(Error::MissingField { key: self.config.tag.to_owned() })
+            },
+        }
+    }
+
+    forward_to_deserialize! {
+        bool
+        usize
+        u8
+        u16
+        u32
+        u64
+        isize
+        i8
+        i16
+        i32
+        i64
+        f32
+        f64
+        char
+        str
+        string
+        unit
+        option
+        bytes
+        unit_struct
+        newtype_struct
+        tuple_struct
+        struct
+        struct_field
+        tuple
+        ignored_any
+    }
+}
+
+/// Errors returned during deserializing from
+/// `application/x-www-form-urlencoded`.
+///
+/// Variants carry the key of the offending pair, when known, as it appeared
+/// in the input, such as `user[address][city]`.
+#[derive(Clone, Debug)]
+pub enum Error {
+    Custom {
+        key: Option<String>,
+        message: String,
+    },
+    MissingField {
+        key: String,
+    },
+    UnknownField {
+        key: String,
+    },
+    UnknownVariant {
+        key: Option<String>,
+        variant: String,
+    },
+    InvalidType {
+        key: Option<String>,
+        expected: de::Type,
+    },
+    InvalidValue {
+        key: Option<String>,
+        value: Option<String>,
+        message: String,
+    },
+    InvalidLength {
+        key: Option<String>,
+        len: usize,
+    },
+    DuplicateKey {
+        key: String,
+    },
+    /// A key or value which is not valid UTF-8 once percent-decoded, with
+    /// `Decoding::Strict`.
+    Utf8 {
+        key: Option<String>,
+        error: str::Utf8Error,
+    },
+    /// Input which is not ASCII, with a legacy encoding.
+    NonAscii {
+        key: Option<String>,
+    },
+    LimitExceeded {
+        key: Option<String>,
+        limit: &'static str,
+        max: usize,
+    },
+    /// A malformed percent-encoded escape, at the given byte offset of the
+    /// input.
+    InvalidEscape {
+        key: Option<String>,
+        offset: usize,
+    },
+    EndOfStream {
+        key: Option<String>,
+    },
+    /// Reading from an `io::Read` failed.
+    Io(Arc<io::Error>),
+}
+
+impl Error {
+    /// The key of the pair that caused this error, if known.
+    pub fn key(&self) -> Option<&str> {
+        match *self {
+            Error::MissingField { ref key } |
+            Error::UnknownField { ref key } |
+            Error::DuplicateKey { ref key } => Some(key),
+            Error::Custom { ref key, .. } |
+            Error::UnknownVariant { ref key, .. } |
+            Error::InvalidType { ref key, .. } |
+            Error::InvalidValue { ref key, .. } |
+            Error::InvalidLength { ref key, .. } |
+            Error::Utf8 { ref key, .. } |
+            Error::NonAscii { ref key } |
+            Error::LimitExceeded { ref key, .. } |
+            Error::InvalidEscape { ref key, .. } |
+            Error::EndOfStream { ref key } => key.as_ref().map(|key| &**key),
+            Error::Io(_) => None,
+        }
+    }
+
+    /// The raw value that failed to parse, if any.
+    pub fn value(&self) -> Option<&str> {
+        match *self {
+            Error::InvalidValue { ref value, .. } => {
+                value.as_ref().map(|value| &**value)
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns an error for a value which failed to parse.
+    fn invalid_value_for(key: &str, value: &str, message: String) -> Self {
+        Error::InvalidValue {
+            key: Some(key.to_owned()),
+            value: Some(value.to_owned()),
+            message,
+        }
+    }
+
+    /// Sets the key of this error, if it is not known yet.
+    fn at(mut self, path: &str) -> Self {
+        match self {
+            Error::Custom { ref mut key, .. } |
+            Error::UnknownVariant { ref mut key, .. } |
+            Error::InvalidType { ref mut key, .. } |
+            Error::InvalidValue { ref mut key, .. } |
+            Error::InvalidLength { ref mut key, .. } |
+            Error::Utf8 { ref mut key, .. } |
+            Error::NonAscii { ref mut key } |
+            Error::LimitExceeded { ref mut key, .. } |
+            Error::InvalidEscape { ref mut key, .. } |
+            Error::EndOfStream { ref mut key } => {
+                if key.is_none() {
+                    *key = Some(path.to_owned());
+                }
+            },
+            Error::MissingField { .. } |
+            Error::UnknownField { .. } |
+            Error::DuplicateKey { .. } |
+            Error::Io(_) => {},
+        }
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            Error::Custom { ref message, .. } => message.fmt(f)?,
+            Error::MissingField { ref key } => {
+                return write!(f, "missing field `{}`", key);
+            },
+            Error::UnknownField { ref key } => {
+                return write!(f, "unknown field `{}`", key);
+            },
+            Error::UnknownVariant { ref variant, .. } => {
+                write!(f, "unknown variant `{}`", variant)?
+            },
+            Error::InvalidType { expected, .. } => {
+                write!(f, "invalid type, expected {:?}", expected)?
+            },
+            Error::InvalidValue { ref value, ref message, .. } => {
+                match *value {
+                    Some(ref value) => {
+                        write!(f, "invalid value `{}`: {}", value, message)?
+                    },
+                    None => write!(f, "invalid value: {}", message)?,
+                }
+            },
+            Error::InvalidLength { len, .. } => {
+                write!(f, "invalid length {}", len)?
+            },
+            Error::DuplicateKey { ref key } => {
+                return write!(f, "duplicate key `{}`", key);
+            },
+            Error::Utf8 { ref error, .. } => {
+                write!(f, "invalid UTF-8: {}", error)?
+            },
+            Error::NonAscii { .. } => {
+                f.write_str("non-ASCII bytes must be percent-encoded when \
+                             the encoding is not UTF-8")?
+            },
+            Error::LimitExceeded { limit, max, .. } => {
+                write!(f, "{} limit of {} exceeded", limit, max)?
+            },
+            Error::InvalidEscape { offset, .. } => {
+                write!(f, "invalid percent-encoded escape at byte {}", offset)?
+            },
+            Error::EndOfStream { .. } => {
+                f.write_str("unexpected end of input")?
+            },
+            Error::Io(ref err) => return write!(f, "I/O error: {}", err),
+        }
+        match self.key() {
+            Some(key) => write!(f, " for key `{}`", key),
+            None => Ok(()),
+        }
+    }
+}
+
+impl error::Error for Error {
+    /// The lower-level source of this error, in the case of a `Utf8` or
+    /// `Io` error.
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Utf8 { ref error, .. } => Some(error),
+            Error::Io(ref err) => Some(&**err),
+            _ => None,
+        }
+    }
+}
+
+/// I/O errors are equal when they are of the same kind.
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Error::Custom { key, message },
+             Error::Custom { key: other_key, message: other_message }) => {
+                key == other_key && message == other_message
+            },
+            (Error::MissingField { key },
+             Error::MissingField { key: other_key }) |
+            (Error::UnknownField { key },
+             Error::UnknownField { key: other_key }) |
+            (Error::DuplicateKey { key },
+             Error::DuplicateKey { key: other_key }) => key == other_key,
+            (Error::UnknownVariant { key, variant },
+             Error::UnknownVariant {
+                 key: other_key,
+                 variant: other_variant,
+             }) => {
+                key == other_key && variant == other_variant
+            },
+            (Error::InvalidType { key, expected },
+             Error::InvalidType {
+                 key: other_key,
+                 expected: other_expected,
+             }) => {
+                key == other_key && expected == other_expected
+            },
+            (Error::InvalidValue { key, value, message },
+             Error::InvalidValue {
+                 key: other_key,
+                 value: other_value,
+                 message: other_message,
+             }) => {
+                key == other_key &&
+                    value == other_value &&
+                    message == other_message
+            },
+            (Error::InvalidLength { key, len },
+             Error::InvalidLength { key: other_key, len: other_len }) => {
+                key == other_key && len == other_len
+            },
+            (Error::Utf8 { key, error },
+             Error::Utf8 { key: other_key, error: other_error }) => {
+                key == other_key && error == other_error
+            },
+            (Error::NonAscii { key },
+             Error::NonAscii { key: other_key }) => key == other_key,
+            (Error::LimitExceeded { key, limit, max },
+             Error::LimitExceeded {
+                 key: other_key,
+                 limit: other_limit,
+                 max: other_max,
+             }) => {
+                key == other_key && limit == other_limit && max == other_max
+            },
+            (Error::InvalidEscape { key, offset },
+             Error::InvalidEscape { key: other_key, offset: other_offset }) => {
+                key == other_key && offset == other_offset
+            },
+            (Error::EndOfStream { key },
+             Error::EndOfStream { key: other_key }) => key == other_key,
+            (Error::Io(err), Error::Io(other_err)) => {
+                err.kind() == other_err.kind()
+            },
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Error {}
+
+impl de::Error for Error {
+    fn custom<T: Into<String>>(msg: T) -> Self {
+        Error::Custom { key: None, message: msg.into() }
+    }
+
+    fn end_of_stream() -> Self {
+        Error::EndOfStream { key: None }
+    }
+
+    fn invalid_type(ty: de::Type) -> Self {
+        Error::InvalidType { key: None, expected: ty }
+    }
+
+    fn invalid_value(msg: &str) -> Self {
+        Error::InvalidValue { key: None, value: None, message: msg.into() }
+    }
+
+    fn invalid_length(len: usize) -> Self {
+        Error::InvalidLength { key: None, len }
+    }
+
+    fn unknown_variant(variant: &str) -> Self {
+        Error::UnknownVariant { key: None, variant: variant.into() }
+    }
+
+    fn unknown_field(field: &str) -> Self {
+        Error::UnknownField { key: field.into() }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Error::MissingField { key: field.into() }
+    }
+
+    fn duplicate_field(field: &'static str) -> Self {
+        Error::DuplicateKey { key: field.into() }
+    }
+}
@@ -0,0 +1,270 @@
+use de::{Config, EmptyValue, Error};
+use serde::de;
+use serde::de::value::ValueDeserializer;
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+/// The value of a `key=value` pair, along with its key for error reporting.
+pub struct Part<'a> {
+    key: Cow<'a, str>,
+    value: Option<Cow<'a, str>>,
+    config: Config,
+}
+
+impl<'a> Part<'a> {
+    pub fn new(key: Cow<'a, str>, value: Cow<'a, str>, config: Config)
+               -> Self {
+        Part {
+            key,
+            value: Some(value),
+            config,
+        }
+    }
+
+    fn take(&mut self) -> Result<Cow<'a, str>, Error> {
+        self.value.take().ok_or_else(|| {
+            Error::EndOfStream { key: Some(self.key.to_string()) }
+        })
+    }
+
+    fn parse<T>(&mut self, ty: &str) -> Result<T, Error>
+        where T: FromStr,
+              T::Err: fmt::Display,
+    {
+        let value = self.take()?;
+        value.parse().map_err(|err| {
+            Error::invalid_value_for(
+                &self.key, &value, format!("not a valid {}: {}", ty, err))
+        })
+    }
+
+    fn deserialize_text<V>(&mut self, mut visitor: V)
+                           -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        match self.take()? {
+            Cow::Borrowed(value) => visitor.visit_str(value),
+            Cow::Owned(value) => visitor.visit_string(value),
+        }
+    }
+}
+
+impl<'a> ValueDeserializer<Error> for Part<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! forward_parsed_value {
+    ($($ty:ident => $method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(&mut self, mut visitor: V) -> Result<V::Value, Error>
+                where V: de::Visitor,
+            {
+                let value = self.parse::<$ty>(stringify!($ty))?;
+                visitor.$visit(value)
+            }
+        )*
+    }
+}
+
+impl<'a> de::Deserializer for Part<'a> {
+    type Error = Error;
+
+    /// Gives the value as a string, unless type inference is enabled and
+    /// it looks like a boolean or a number written in canonical form, so
+    /// that values such as `01234` or `+5` are kept as they are, as well as
+    /// integers and floats out of range.
+    fn deserialize<V>(&mut self, mut visitor: V) -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        if !self.config.infer_types {
+            return self.deserialize_text(visitor);
+        }
+        let value = self.take()?;
+        match &*value {
+            "true" => return visitor.visit_bool(true),
+            "false" => return visitor.visit_bool(false),
+            _ => {},
+        }
+        if is_canonical_integer(&value) {
+            if let Ok(value) = value.parse() {
+                return visitor.visit_u64(value);
+            }
+            if let Ok(value) = value.parse() {
+                return visitor.visit_i64(value);
+            }
+        } else if looks_like_float(&value) {
+            match value.parse::<f64>() {
+                Ok(float) if float.is_finite() => {
+                    return visitor.visit_f64(float);
+                },
+                _ => {},
+            }
+        }
+        self.value = Some(value);
+        self.deserialize_text(visitor)
+    }
+
+    fn deserialize_str<V>(&mut self, visitor: V) -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        self.deserialize_text(visitor)
+    }
+
+    fn deserialize_string<V>(&mut self, visitor: V)
+                             -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        self.deserialize_text(visitor)
+    }
+
+    fn deserialize_struct_field<V>(&mut self, visitor: V)
+                                   -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        self.deserialize_text(visitor)
+    }
+
+    /// Deserializes an empty value according to the configured
+    /// `EmptyValue` policy.
+    fn deserialize_option<V>(&mut self, mut visitor: V)
+                             -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        let empty = self.value.as_ref().is_some_and(|value| value.is_empty());
+        match self.config.empty_value {
+            EmptyValue::None if empty => visitor.visit_none(),
+            EmptyValue::Error if empty => {
+                Err(Error::invalid_value_for(
+                    &self.key, "", "empty value".into()))
+            },
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(
+            &mut self, _name: &'static str, mut visitor: V)
+            -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+            &mut self,
+            _name: &'static str,
+            _variants: &'static [&'static str],
+            mut visitor: V)
+            -> Result<V::Value, Error>
+        where V: de::EnumVisitor,
+    {
+        visitor.visit(self)
+    }
+
+    forward_parsed_value! {
+        bool => deserialize_bool => visit_bool,
+        usize => deserialize_usize => visit_usize,
+        u8 => deserialize_u8 => visit_u8,
+        u16 => deserialize_u16 => visit_u16,
+        u32 => deserialize_u32 => visit_u32,
+        u64 => deserialize_u64 => visit_u64,
+        isize => deserialize_isize => visit_isize,
+        i8 => deserialize_i8 => visit_i8,
+        i16 => deserialize_i16 => visit_i16,
+        i32 => deserialize_i32 => visit_i32,
+        i64 => deserialize_i64 => visit_i64,
+        f32 => deserialize_f32 => visit_f32,
+        f64 => deserialize_f64 => visit_f64,
+        char => deserialize_char => visit_char,
+    }
+
+    forward_to_deserialize! {
+        unit
+        seq
+        seq_fixed_size
+        bytes
+        map
+        unit_struct
+        tuple_struct
+        struct
+        tuple
+        ignored_any
+    }
+}
+
+/// Returns whether a value is an integer without an explicit `+` sign nor
+/// leading zeros, apart from `0` itself.
+fn is_canonical_integer(value: &str) -> bool {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    !digits.is_empty() &&
+        digits.bytes().all(|b| b.is_ascii_digit()) &&
+        !has_leading_zero(digits)
+}
+
+/// Returns whether a value is written as a decimal number, ruling out
+/// values such as `inf` or `NaN` which parse as floats too, as well as
+/// explicit `+` signs and leading zeros.
+fn looks_like_float(value: &str) -> bool {
+    let unsigned = value.strip_prefix('-').unwrap_or(value);
+    unsigned.bytes().next().is_some_and(|b| b.is_ascii_digit()) &&
+        !has_leading_zero(unsigned) &&
+        value.bytes().all(|b| {
+            matches!(b, b'0'..=b'9' | b'.' | b'e' | b'E' | b'+' | b'-')
+        })
+}
+
+/// Returns whether a number starts with a zero followed by another digit.
+fn has_leading_zero(unsigned: &str) -> bool {
+    let bytes = unsigned.as_bytes();
+    bytes.len() > 1 && bytes[0] == b'0' && bytes[1].is_ascii_digit()
+}
+
+impl<'a> de::VariantVisitor for Part<'a> {
+    type Error = Error;
+
+    fn visit_variant<V>(&mut self) -> Result<V, Error>
+        where V: de::Deserialize,
+    {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn visit_unit(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Returns an error, which is custom as `de::Type` has no newtype
+    /// variant type.
+    fn visit_newtype<T>(&mut self) -> Result<T, Error>
+        where T: de::Deserialize,
+    {
+        Err(Error::Custom {
+            key: Some(self.key.to_string()),
+            message: "invalid type, expected a newtype variant".to_owned(),
+        })
+    }
+
+    fn visit_tuple<V>(&mut self, _len: usize, _visitor: V)
+                      -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        Err(Error::InvalidType {
+            key: Some(self.key.to_string()),
+            expected: de::Type::TupleVariant,
+        })
+    }
+
+    fn visit_struct<V>(
+            &mut self, _fields: &'static [&'static str], _visitor: V)
+            -> Result<V::Value, Error>
+        where V: de::Visitor,
+    {
+        Err(Error::InvalidType {
+            key: Some(self.key.to_string()),
+            expected: de::Type::StructVariant,
+        })
+    }
+}
@@ -0,0 +1,109 @@
+use de::{Config, Error};
+use de::decode::decode_pair;
+use std::io::{self, BufRead, Read};
+use std::sync::Arc;
+
+/// The pairs read from an `io::Read`, one pair at a time.
+///
+/// Bytes are read in chunks and accumulated until a whole pair is
+/// available, so that separators and escapes split across chunks are
+/// decoded as if the input was contiguous. A pair longer than the limits
+/// allow is cut short, and left for them to reject once decoded.
+pub struct ReaderPairs<R> {
+    reader: io::BufReader<R>,
+    pair: Vec<u8>,
+    max_len: Option<usize>,
+    offset: usize,
+    config: Config,
+}
+
+impl<R: io::Read> ReaderPairs<R> {
+    pub fn new(reader: R, config: Config) -> Self {
+        ReaderPairs {
+            reader: io::BufReader::new(reader),
+            pair: vec![],
+            max_len: config.limits.max_encoded_pair_len(),
+            offset: 0,
+            config,
+        }
+    }
+}
+
+impl<R: io::Read> Iterator for ReaderPairs<R> {
+    type Item = Result<(String, String), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.pair.clear();
+            let separators = self.config.pair_separators;
+            let read = match self.max_len {
+                Some(max_len) => {
+                    // One more byte than allowed, so that a pair of the
+                    // maximum length is still read with its separator.
+                    let limit = max_len.saturating_add(1) as u64;
+                    read_until_any(
+                        &mut (&mut self.reader).take(limit),
+                        separators,
+                        &mut self.pair)
+                },
+                None => {
+                    read_until_any(&mut self.reader, separators, &mut self.pair)
+                },
+            };
+            let offset = self.offset;
+            match read {
+                Ok(0) => return None,
+                Ok(len) => self.offset += len,
+                Err(err) => return Some(Err(Error::Io(Arc::new(err)))),
+            }
+            if self.pair.last().is_some_and(|b| separators.contains(b)) {
+                self.pair.pop();
+            }
+            let charset = self.config.charset;
+            if charset.check(&self.pair).is_err() {
+                return Some(Err(Error::NonAscii { key: None }));
+            }
+            match decode_pair(&self.pair, offset, self.config, charset) {
+                Ok(Some((key, value))) => {
+                    return Some(Ok((key.into_owned(), value.into_owned())));
+                },
+                Ok(None) => {},
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+/// Reads bytes until any of the given separators is found, including it,
+/// as `BufRead::read_until` does with a single one.
+fn read_until_any<R: BufRead>(reader: &mut R, separators: &[u8],
+                              output: &mut Vec<u8>)
+                              -> io::Result<usize> {
+    let mut read = 0;
+    loop {
+        let (done, used) = {
+            let available = match reader.fill_buf() {
+                Ok(available) => available,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {
+                    continue;
+                },
+                Err(err) => return Err(err),
+            };
+            match available.iter().position(|b| separators.contains(b)) {
+                Some(end) => {
+                    output.extend_from_slice(&available[..=end]);
+                    (true, end + 1)
+                },
+                None => {
+                    output.extend_from_slice(available);
+                    (available.is_empty(), available.len())
+                },
+            }
+        };
+        reader.consume(used);
+        read += used;
+        if done {
+            return Ok(read);
+        }
+    }
+}
@@ -0,0 +1,237 @@
+//! A dynamic representation of `application/x-www-form-urlencoded` data.
+
+use de;
+use serde::{de as serde_de, ser as serde_ser};
+use std::iter::FromIterator;
+use std::slice;
+use std::vec;
+
+/// An insertion-ordered multimap of form pairs, for data whose shape is not
+/// known at compile time.
+///
+/// A `Form` is serialized as the sequence of its pairs and deserialized
+/// from one, so that the order of the pairs is preserved.
+///
+/// ```
+/// use serde_urlencoded::Form;
+///
+/// let mut form: Form = serde_urlencoded::from_str("tag=a&page=2&tag=b")
+///     .unwrap();
+///
+/// assert_eq!(form.get("tag"), Some("a"));
+/// assert_eq!(form.get_all("tag"), vec!["a", "b"]);
+/// assert_eq!(form.get_as::<u32>("page"), Ok(2));
+///
+/// form.insert("page", "3");
+/// form.append("sort", "desc");
+///
+/// assert_eq!(
+///     serde_urlencoded::to_string(&form),
+///     Ok("tag=a&page=3&tag=b&sort=desc".to_owned()));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Form {
+    pairs: Vec<(String, String)>,
+}
+
+impl Form {
+    /// Returns an empty `Form`.
+    pub fn new() -> Self {
+        Form::default()
+    }
+
+    /// Returns the number of pairs.
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Returns whether there are no pairs.
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// Returns the first value of the given key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|&(k, _)| k == key)
+            .map(|(_, value)| &**value)
+    }
+
+    /// Returns all the values of the given key, in order.
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.pairs
+            .iter()
+            .filter(|&(k, _)| k == key)
+            .map(|(_, value)| &**value)
+            .collect()
+    }
+
+    /// Deserializes the values of the given key, as a field of a struct
+    /// would be: sequences receive all of them, other types the first one
+    /// as returned by `get`, and options are `None` if there are none.
+    pub fn get_as<T: serde_de::Deserialize>(&self, key: &str)
+                                            -> Result<T, de::Error> {
+        de::from_values(key, self.get_all(key))
+    }
+
+    /// Sets the value of the given key, replacing all its values in place
+    /// of the first one, or appending it if there is none.
+    pub fn insert<K, V>(&mut self, key: K, value: V)
+        where K: Into<String>,
+              V: Into<String>,
+    {
+        let key = key.into();
+        let mut value = Some(value.into());
+        self.pairs.retain_mut(|(k, v)| {
+            if *k != key {
+                return true;
+            }
+            match value.take() {
+                Some(value) => {
+                    *v = value;
+                    true
+                },
+                None => false,
+            }
+        });
+        if let Some(value) = value {
+            self.pairs.push((key, value));
+        }
+    }
+
+    /// Appends a pair, keeping the existing values of the key.
+    pub fn append<K, V>(&mut self, key: K, value: V)
+        where K: Into<String>,
+              V: Into<String>,
+    {
+        self.pairs.push((key.into(), value.into()));
+    }
+
+    /// Removes all the values of the given key, returning them in order.
+    pub fn remove(&mut self, key: &str) -> Vec<String> {
+        let mut removed = vec![];
+        let mut kept = Vec::with_capacity(self.pairs.len());
+        for (k, value) in self.pairs.drain(..) {
+            if k == key {
+                removed.push(value);
+            } else {
+                kept.push((k, value));
+            }
+        }
+        self.pairs = kept;
+        removed
+    }
+
+    /// Returns an iterator over the pairs, in order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.pairs.iter())
+    }
+}
+
+/// An iterator over the pairs of a `Form`.
+pub struct Iter<'a>(slice::Iter<'a, (String, String)>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (&**key, &**value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<'a> IntoIterator for &'a Form {
+    type Item = (&'a str, &'a str);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl IntoIterator for Form {
+    type Item = (String, String);
+    type IntoIter = vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.into_iter()
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for Form
+    where K: Into<String>,
+          V: Into<String>,
+{
+    fn from_iter<I>(pairs: I) -> Self
+        where I: IntoIterator<Item = (K, V)>,
+    {
+        let mut form = Form::new();
+        form.extend(pairs);
+        form
+    }
+}
+
+impl<K, V> Extend<(K, V)> for Form
+    where K: Into<String>,
+          V: Into<String>,
+{
+    fn extend<I>(&mut self, pairs: I)
+        where I: IntoIterator<Item = (K, V)>,
+    {
+        for (key, value) in pairs {
+            self.append(key, value);
+        }
+    }
+}
+
+impl serde_ser::Serialize for Form {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: serde_ser::Serializer,
+    {
+        let mut state = serializer.serialize_seq(Some(self.pairs.len()))?;
+        for pair in &self.pairs {
+            serializer.serialize_seq_elt(&mut state, pair)?;
+        }
+        serializer.serialize_seq_end(state)
+    }
+}
+
+impl serde_de::Deserialize for Form {
+    fn deserialize<D>(deserializer: &mut D) -> Result<Self, D::Error>
+        where D: serde_de::Deserializer,
+    {
+        deserializer.deserialize_seq(FormVisitor)
+    }
+}
+
+struct FormVisitor;
+
+impl serde_de::Visitor for FormVisitor {
+    type Value = Form;
+
+    fn visit_seq<V>(&mut self, mut visitor: V) -> Result<Form, V::Error>
+        where V: serde_de::SeqVisitor,
+    {
+        let mut form = Form::new();
+        while let Some((key, value)) = visitor.visit::<(String, String)>()? {
+            form.pairs.push((key, value));
+        }
+        visitor.end()?;
+        Ok(form)
+    }
+
+    fn visit_map<V>(&mut self, mut visitor: V) -> Result<Form, V::Error>
+        where V: serde_de::MapVisitor,
+    {
+        let mut form = Form::new();
+        while let Some((key, value)) = visitor.visit::<String, String>()? {
+            form.pairs.push((key, value));
+        }
+        visitor.end()?;
+        Ok(form)
+    }
+}
@@ -1,11 +1,26 @@
 //! `x-www-form-urlencoded` meets Serde
+//!
+//! Forms in legacy character encodings, such as Windows-1252 or Shift_JIS,
+//! are supported with the `query_encoding` feature.
 
+#[cfg(feature = "query_encoding")]
+extern crate encoding;
 #[macro_use]
 extern crate serde;
 extern crate url;
 
+mod charset;
 pub mod de;
+pub mod form;
 pub mod ser;
+mod url_ext;
 
-pub use de::{Deserializer, from_bytes, from_str};
-pub use ser::{Serializer, to_string};
+pub use de::{Deserializer, from_bytes, from_pairs, from_reader, from_str};
+pub use ser::{Serializer, to_fmt_writer, to_pairs, to_string, to_writer};
+pub use form::Form;
+pub use url_ext::UrlExt;
+
+#[cfg(feature = "query_encoding")]
+pub use de::from_bytes_with_encoding;
+#[cfg(feature = "query_encoding")]
+pub use ser::to_string_with_encoding;
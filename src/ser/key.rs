@@ -1,5 +1,5 @@
 use serde::{Serialize, Serializer};
-use ser::Error;
+use ser::{Error, Kind, Position};
 use std::borrow::Cow;
 use std::str;
 
@@ -29,7 +29,7 @@ impl<'key> Serializer for MapKeySerializer<'key> {
     type StructVariantState = ();
 
     fn serialize_bool(&mut self, _v: bool) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Bool))
     }
 
     fn serialize_isize(&mut self, v: isize) -> Result<(), Error> {
@@ -96,7 +96,7 @@ impl<'key> Serializer for MapKeySerializer<'key> {
     }
 
     fn serialize_unit(&mut self) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Unit))
     }
 
     fn serialize_unit_struct(
@@ -131,21 +131,21 @@ impl<'key> Serializer for MapKeySerializer<'key> {
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::NewtypeVariant))
     }
 
     fn serialize_none(&mut self) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Option))
     }
 
     fn serialize_some<T>(&mut self, _value: T) -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Option))
     }
 
     fn serialize_seq(&mut self, _len: Option<usize>) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Seq))
     }
 
     fn serialize_seq_elt<T>(
@@ -153,19 +153,19 @@ impl<'key> Serializer for MapKeySerializer<'key> {
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Seq))
     }
 
     fn serialize_seq_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Seq))
     }
 
     fn serialize_seq_fixed_size(&mut self, _size: usize) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Seq))
     }
 
     fn serialize_tuple(&mut self, _len: usize) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Tuple))
     }
 
     fn serialize_tuple_elt<T>(
@@ -173,17 +173,17 @@ impl<'key> Serializer for MapKeySerializer<'key> {
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Tuple))
     }
 
     fn serialize_tuple_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Tuple))
     }
 
     fn serialize_tuple_struct(
             &mut self, _name: &'static str, _len: usize)
             -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::TupleStruct))
     }
 
     fn serialize_tuple_struct_elt<T>(
@@ -191,11 +191,11 @@ impl<'key> Serializer for MapKeySerializer<'key> {
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::TupleStruct))
     }
 
     fn serialize_tuple_struct_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::TupleStruct))
     }
 
     fn serialize_tuple_variant(
@@ -205,7 +205,7 @@ impl<'key> Serializer for MapKeySerializer<'key> {
             _variant: &'static str,
             _len: usize)
             -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::TupleVariant))
     }
 
     fn serialize_tuple_variant_elt<T>(
@@ -213,15 +213,15 @@ impl<'key> Serializer for MapKeySerializer<'key> {
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::TupleVariant))
     }
 
     fn serialize_tuple_variant_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::TupleVariant))
     }
 
     fn serialize_map(&mut self, _len: Option<usize>) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Map))
     }
 
     fn serialize_map_key<T>(
@@ -229,7 +229,7 @@ impl<'key> Serializer for MapKeySerializer<'key> {
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Map))
     }
 
     fn serialize_map_value<T>(
@@ -237,17 +237,17 @@ impl<'key> Serializer for MapKeySerializer<'key> {
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Map))
     }
 
     fn serialize_map_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Map))
     }
 
     fn serialize_struct(
             &mut self, _name: &'static str, _len: usize)
             -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Struct))
     }
 
     fn serialize_struct_elt<T>(
@@ -255,11 +255,11 @@ impl<'key> Serializer for MapKeySerializer<'key> {
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Struct))
     }
 
     fn serialize_struct_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::Struct))
     }
 
     fn serialize_struct_variant(
@@ -269,24 +269,24 @@ impl<'key> Serializer for MapKeySerializer<'key> {
             _variant: &'static str,
             _len: usize)
             -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::StructVariant))
     }
 
     fn serialize_struct_variant_elt<T>(
             &mut self, _state: &mut (), _key: &'static str, _value: T)
             -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::StructVariant))
     }
 
     fn serialize_struct_variant_end(
             &mut self, _state: ())
             -> Result<(), Error> {
-        Err(Error::unsupported_key())
+        Err(Error::unsupported_key(Kind::StructVariant))
     }
 }
 
 impl Error {
-    fn unsupported_key() -> Self {
-        Error::Custom("unsupported key".into())
+    fn unsupported_key(kind: Kind) -> Self {
+        Error::Unsupported { position: Position::Key, kind, path: None }
     }
 }
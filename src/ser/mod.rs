@@ -2,15 +2,25 @@
 
 mod key;
 mod pair;
+mod sink;
 mod value;
 
 use serde::ser;
 use std::borrow::Cow;
 use std::error;
 use std::fmt;
+use std::io;
 use std::str;
-use url::form_urlencoded::Serializer as UrlEncodedSerializer;
-use url::form_urlencoded::Target as UrlEncodedTarget;
+use std::sync::Arc;
+use url::percent_encoding::percent_encode_byte;
+
+use charset::Charset;
+#[cfg(feature = "query_encoding")]
+use encoding::EncodingRef;
+
+use self::sink::{ExtendSink, FmtSink, IoSink, TaggedSink};
+
+pub use self::sink::Sink;
 
 /// Serializes a value into a `application/x-wwww-url-encoded` `String` buffer.
 ///
@@ -27,12 +37,435 @@ use url::form_urlencoded::Target as UrlEncodedTarget;
 ///     Ok("bread=baguette&cheese=comt%C3%A9&meat=ham&fat=butter".to_owned()));
 /// ```
 pub fn to_string<T: ser::Serialize>(input: &T) -> Result<String, Error> {
-    let mut output = String::new();
+    Config::new().to_string(input)
+}
+
+/// Serializes a value into a `application/x-wwww-url-encoded` `String` buffer,
+/// encoding names and values in the given character encoding.
+///
+/// Characters that cannot be encoded are replaced with numeric character
+/// references, as browsers do.
+///
+/// ```
+/// extern crate encoding;
+/// extern crate serde_urlencoded;
+///
+/// use encoding::all::WINDOWS_1252;
+///
+/// # fn main() {
+/// assert_eq!(
+///     serde_urlencoded::to_string_with_encoding(
+///         &[("cheese", "comté"), ("price", "3€"), ("tea", "茶")],
+///         WINDOWS_1252),
+///     Ok("cheese=comt%E9&price=3%80&tea=%26%2333590%3B".to_owned()));
+/// # }
+/// ```
+#[cfg(feature = "query_encoding")]
+pub fn to_string_with_encoding<T>(input: &T, encoding: EncodingRef)
+                                  -> Result<String, Error>
+    where T: ser::Serialize,
+{
+    Config::new().encoding(encoding).to_string(input)
+}
+
+/// Serializes a value into a `application/x-wwww-url-encoded` stream,
+/// writing each pair as soon as it is serialized.
+///
+/// ```
+/// let mut body = vec![];
+/// serde_urlencoded::to_writer(&mut body, &[("bread", "baguette")]).unwrap();
+///
+/// assert_eq!(body, b"bread=baguette");
+/// ```
+pub fn to_writer<W, T>(writer: W, input: &T) -> Result<(), Error>
+    where W: io::Write,
+          T: ser::Serialize,
+{
+    Config::new().to_writer(writer, input)
+}
+
+/// Serializes a value into a `application/x-wwww-url-encoded` formatter,
+/// writing each pair as soon as it is serialized.
+pub fn to_fmt_writer<W, T>(writer: W, input: &T) -> Result<(), Error>
+    where W: fmt::Write,
+          T: ser::Serialize,
+{
+    Config::new().to_fmt_writer(writer, input)
+}
+
+/// Serializes a value into the pairs it is made of, without encoding them.
+///
+/// ```
+/// let params = &[("name", "Jane Doe"), ("ids", "1,2")];
+///
+/// assert_eq!(
+///     serde_urlencoded::to_pairs(params),
+///     Ok(vec![
+///         ("name".to_owned(), "Jane Doe".to_owned()),
+///         ("ids".to_owned(), "1,2".to_owned()),
+///     ]));
+/// ```
+pub fn to_pairs<T: ser::Serialize>(input: &T)
+                                   -> Result<Vec<(String, String)>, Error> {
+    Config::new().to_pairs(input)
+}
+
+/// Serializes a value into the pairs it is made of, without encoding them,
+/// giving them to an `Extend` collection as soon as they are serialized.
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// let mut pairs = BTreeMap::new();
+/// serde_urlencoded::ser::to_pairs_into(&mut pairs, &[("page", 2)]).unwrap();
+///
+/// assert_eq!(pairs["page"], "2");
+/// ```
+pub fn to_pairs_into<E, T>(target: &mut E, input: &T) -> Result<(), Error>
+    where E: Extend<(String, String)>,
+          T: ser::Serialize,
+{
+    Config::new().to_pairs_into(target, input)
+}
+
+/// Options used when serializing to `application/x-www-form-urlencoded`.
+///
+/// ```
+/// use serde_urlencoded::ser::{ArrayStyle, Config};
+///
+/// let params = &[("ids", vec![1, 2])];
+///
+/// assert_eq!(
+///     Config::new().array_style(ArrayStyle::Indices).to_string(params),
+///     Ok("ids%5B0%5D=1&ids%5B1%5D=2".to_owned()));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Config {
+    array_style: ArrayStyle,
+    nesting: Nesting,
+    tag: &'static str,
+    encode_set: EncodeSet,
+    charset: Charset,
+    pair_separator: u8,
+    key_value_separator: u8,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            array_style: ArrayStyle::default(),
+            nesting: Nesting::default(),
+            tag: "type",
+            encode_set: EncodeSet::default(),
+            charset: Charset::utf8(),
+            pair_separator: b'&',
+            key_value_separator: b'=',
+        }
+    }
+}
+
+impl Config {
+    /// Returns the default configuration.
+    pub fn new() -> Self {
+        Config::default()
+    }
+
+    /// Sets how sequence values are serialized, `ArrayStyle::Repeat` by
+    /// default.
+    pub fn array_style(mut self, array_style: ArrayStyle) -> Self {
+        self.array_style = array_style;
+        self
+    }
+
+    /// Sets how maps and structs nested in values are serialized,
+    /// `Nesting::Disabled` by default.
+    ///
+    /// ```
+    /// use serde_urlencoded::ser::{Config, Nesting};
+    /// use std::collections::BTreeMap;
+    ///
+    /// let mut address = BTreeMap::new();
+    /// address.insert("city", "Paris");
+    /// address.insert("zip", "75001");
+    ///
+    /// assert_eq!(
+    ///     Config::new()
+    ///         .nesting(Nesting::Brackets)
+    ///         .to_string(&[("address", address)]),
+    ///     Ok("address%5Bcity%5D=Paris&address%5Bzip%5D=75001".to_owned()));
+    /// ```
+    pub fn nesting(mut self, nesting: Nesting) -> Self {
+        self.nesting = nesting;
+        self
+    }
+
+    /// Sets the key holding the variant name of top-level enums, `type` by
+    /// default.
+    ///
+    /// ```
+    /// use serde_urlencoded::ser::Config;
+    ///
+    /// let mut params = std::collections::BTreeMap::new();
+    /// params.insert("number", "4242");
+    ///
+    /// assert_eq!(
+    ///     Config::new().tag("kind").to_string(&Ok::<_, ()>(params)),
+    ///     Ok("kind=Ok&number=4242".to_owned()));
+    /// ```
+    pub fn tag(mut self, tag: &'static str) -> Self {
+        self.tag = tag;
+        self
+    }
+
+    /// Sets which characters of keys and values are percent-encoded when
+    /// writing them, `EncodeSet::Form` by default.
+    ///
+    /// Pairs serialized with `to_pairs` are never encoded.
+    ///
+    /// ```
+    /// use serde_urlencoded::ser::{Config, EncodeSet};
+    ///
+    /// let params = &[("q", "rust serde~1"), ("sort", "name:asc")];
+    ///
+    /// assert_eq!(
+    ///     Config::new().encode_set(EncodeSet::Rfc3986).to_string(params),
+    ///     Ok("q=rust%20serde~1&sort=name%3Aasc".to_owned()));
+    /// assert_eq!(
+    ///     Config::new().encode_set(EncodeSet::Custom(":")).to_string(params),
+    ///     Ok("q=rust%20serde%7E1&sort=name:asc".to_owned()));
+    /// ```
+    pub fn encode_set(mut self, encode_set: EncodeSet) -> Self {
+        self.encode_set = encode_set;
+        self
+    }
+
+    /// Sets the ASCII character written between pairs, `&` by default.
+    ///
+    /// Separators appearing in keys and values are always
+    /// percent-encoded, whatever the encode set.
+    ///
+    /// ```
+    /// use serde_urlencoded::ser::Config;
+    ///
+    /// let params = &[("id", "7"), ("name", "a|b")];
+    ///
+    /// assert_eq!(
+    ///     Config::new()
+    ///         .pair_separator(b'|')
+    ///         .key_value_separator(b':')
+    ///         .to_string(params),
+    ///     Ok("id:7|name:a%7Cb".to_owned()));
+    /// ```
+    ///
+    /// Serializing fails if both separators are the same.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the separator is not ASCII punctuation other than `%`, as
+    /// it could not be told apart from keys, values and escapes.
+    pub fn pair_separator(mut self, separator: u8) -> Self {
+        assert!(is_separator(separator),
+                "pair separator must be ASCII punctuation other than `%`");
+        self.pair_separator = separator;
+        self
+    }
+
+    /// Sets the ASCII character written between keys and values, `=` by
+    /// default.
+    ///
+    /// Serializing fails if both separators are the same.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the separator is not ASCII punctuation other than `%`.
+    pub fn key_value_separator(mut self, separator: u8) -> Self {
+        assert!(is_separator(separator),
+                "key/value separator must be ASCII punctuation other than \
+                 `%`");
+        self.key_value_separator = separator;
+        self
+    }
+
+    /// Sets the character encoding of names and values when writing them,
+    /// UTF-8 by default.
+    ///
+    /// Pairs serialized with `to_pairs` are never encoded.
+    #[cfg(feature = "query_encoding")]
+    pub fn encoding(mut self, encoding: EncodingRef) -> Self {
+        self.charset = Charset::new(encoding);
+        self
+    }
+
+    /// Serializes a value into a `application/x-wwww-url-encoded` `String`
+    /// buffer using this configuration.
+    pub fn to_string<T: ser::Serialize>(&self, input: &T)
+                                        -> Result<String, Error> {
+        let mut output = String::new();
+        self.to_fmt_writer(&mut output, input)?;
+        Ok(output)
+    }
+
+    /// Serializes a value into a `application/x-wwww-url-encoded` stream
+    /// using this configuration.
+    pub fn to_writer<W, T>(&self, writer: W, input: &T) -> Result<(), Error>
+        where W: io::Write,
+              T: ser::Serialize,
+    {
+        self.check_separators()?;
+        let mut sink = IoSink::new(writer, *self);
+        input.serialize(&mut Serializer::with_config(&mut sink, *self))?;
+        sink.flush()
+    }
+
+    /// Serializes a value into a `application/x-wwww-url-encoded` formatter
+    /// using this configuration.
+    pub fn to_fmt_writer<W, T>(&self, writer: W, input: &T)
+                               -> Result<(), Error>
+        where W: fmt::Write,
+              T: ser::Serialize,
+    {
+        self.check_separators()?;
+        let mut sink = FmtSink::new(writer, *self);
+        input.serialize(&mut Serializer::with_config(&mut sink, *self))
+    }
+
+    /// Serializes a value into the pairs it is made of, without encoding
+    /// them, using this configuration.
+    pub fn to_pairs<T: ser::Serialize>(&self, input: &T)
+                                       -> Result<Vec<(String, String)>, Error> {
+        let mut pairs = vec![];
+        self.to_pairs_into(&mut pairs, input)?;
+        Ok(pairs)
+    }
+
+    /// Serializes a value into the pairs it is made of, without encoding
+    /// them, giving them to an `Extend` collection using this configuration.
+    pub fn to_pairs_into<E, T>(&self, target: &mut E, input: &T)
+                               -> Result<(), Error>
+        where E: Extend<(String, String)>,
+              T: ser::Serialize,
+    {
+        let mut sink = ExtendSink(target);
+        input.serialize(&mut Serializer::with_config(&mut sink, *self))
+    }
+
+    /// Checks that the separators differ, which cannot be done as they are
+    /// set without depending on the order of the calls.
+    fn check_separators(&self) -> Result<(), Error> {
+        if self.pair_separator == self.key_value_separator {
+            return Err(Error::Custom(
+                "pair and key/value separators must differ".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether a byte may separate pairs or keys from values, which
+/// requires it to be ASCII punctuation other than the `%` of escapes.
+pub(crate) fn is_separator(byte: u8) -> bool {
+    byte.is_ascii_punctuation() && byte != b'%'
+}
+
+/// How sequence values are serialized.
+///
+/// Elements may only be maps or structs with `ArrayStyle::Indices`, when a
+/// `Nesting` mode is configured, as the fields of different elements could
+/// not be told apart with the other styles.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArrayStyle {
+    /// One pair per element, repeating the key: `ids=1&ids=2`.
+    #[default]
+    Repeat,
+    /// One pair per element, with `[]` appended to the key:
+    /// `ids[]=1&ids[]=2`.
+    Brackets,
+    /// One pair per element, with the element index appended to the key:
+    /// `ids[0]=1&ids[1]=2`.
+    Indices,
+    /// A single pair with the elements separated by commas: `ids=1,2`.
+    ///
+    /// Commas within elements are percent-encoded when writing strings,
+    /// and are an error when serializing into pairs, as the elements could
+    /// not be told apart.
+    Comma,
+}
+
+/// Which characters of keys and values are percent-encoded when writing
+/// them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EncodeSet {
+    /// The WHATWG `application/x-www-form-urlencoded` serializer: only
+    /// ASCII alphanumerics and `*-._` are left unescaped, and spaces are
+    /// written as `+`: `q=rust+serde%7E1`.
+    #[default]
+    Form,
+    /// RFC 3986 query encoding, as required by OAuth 1.0 and AWS
+    /// signatures: only ASCII alphanumerics and `-._~` are left unescaped,
+    /// and spaces are written as `%20`: `q=rust%20serde~1`.
+    Rfc3986,
+    /// Only ASCII alphanumerics and the given ASCII characters are left
+    /// unescaped, and spaces are written as `%20` unless given.
+    Custom(&'static str),
+}
+
+impl EncodeSet {
+    fn is_unescaped(&self, byte: u8) -> bool {
+        if byte.is_ascii_alphanumeric() {
+            return true;
+        }
+        match *self {
+            EncodeSet::Form => matches!(byte, b'*' | b'-' | b'.' | b'_'),
+            EncodeSet::Rfc3986 => matches!(byte, b'-' | b'.' | b'_' | b'~'),
+            EncodeSet::Custom(chars) => {
+                byte.is_ascii() && chars.as_bytes().contains(&byte)
+            },
+        }
+    }
+
+    /// Writes the given bytes percent-encoded, in chunks, escaping the
+    /// given separators too.
+    fn encode<F>(&self, input: &[u8], separators: &[u8], write: &mut F)
+                 -> Result<(), Error>
+        where F: FnMut(&str) -> Result<(), Error>,
     {
-        let mut urlencoder = UrlEncodedSerializer::new(&mut output);
-        try!(input.serialize(&mut Serializer::new(&mut urlencoder)));
+        let mut rest = input;
+        while let Some((&byte, tail)) = rest.split_first() {
+            let len = rest.iter()
+                .position(|b| {
+                    !self.is_unescaped(*b) || separators.contains(b)
+                })
+                .unwrap_or(rest.len());
+            if len > 0 {
+                // Unescaped bytes are ASCII.
+                write(str::from_utf8(&rest[..len]).unwrap_or_default())?;
+                rest = &rest[len..];
+            } else {
+                if byte == b' ' && *self == EncodeSet::Form &&
+                    !separators.contains(&b'+') {
+                    write("+")?;
+                } else {
+                    write(percent_encode_byte(byte))?;
+                }
+                rest = tail;
+            }
+        }
+        Ok(())
     }
-    Ok(output)
+}
+
+/// How the keys of maps and structs nested in values are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Nesting {
+    /// Nested maps and structs are not supported.
+    #[default]
+    Disabled,
+    /// Nested fields are serialized with their name between brackets
+    /// after the key of their parent, to any depth:
+    /// `user[address][city]=Paris`.
+    Brackets,
+    /// Nested fields are serialized with their name after the key of their
+    /// parent and a dot, to any depth: `user.address.city=Paris`.
+    Dots,
 }
 
 /// A serializer for the `application/x-www-form-urlencoded` format.
@@ -44,23 +477,83 @@ pub fn to_string<T: ser::Serialize>(input: &T) -> Result<String, Error> {
 ///   unit structs and unit variants.
 ///
 /// * Newtype structs defer to their inner values.
-pub struct Serializer<'output, T: 'output + UrlEncodedTarget> {
-    urlencoder: &'output mut UrlEncodedSerializer<T>
+///
+/// * Top-level unit, newtype and struct variants are serialized as a pair
+///   holding the variant name under the configured tag key, followed by the
+///   pairs of their contents, if any.
+///
+/// * Sequence and tuple values are serialized according to the configured
+///   `ArrayStyle`, as repeated pairs sharing the same key by default.
+///
+/// * Map and struct values are only supported when a `Nesting` mode is
+///   configured.
+///
+/// * Pairs are given to a `Sink`, such as a `url::form_urlencoded::Serializer`.
+pub struct Serializer<'output, S: 'output + Sink> {
+    sink: &'output mut S,
+    config: Config,
 }
 
-impl<'output, T: 'output + UrlEncodedTarget> Serializer<'output, T> {
+impl<'output, S: 'output + Sink> Serializer<'output, S> {
     /// Returns a new `Serializer`.
-    pub fn new(urlencoder: &'output mut UrlEncodedSerializer<T>) -> Self {
-        Serializer { urlencoder: urlencoder }
+    pub fn new(sink: &'output mut S) -> Self {
+        Serializer::with_config(sink, Config::default())
+    }
+
+    /// Returns a new `Serializer` using the given configuration.
+    pub fn with_config(sink: &'output mut S, config: Config) -> Self {
+        Serializer { sink, config }
     }
 }
 
 /// Errors returned during serializing to `application/x-www-form-urlencoded`.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug)]
 pub enum Error {
     Custom(Cow<'static, str>),
     InvalidValue(Cow<'static, str>),
     Utf8(str::Utf8Error),
+    /// Writing to an `io::Write` failed.
+    Io(Arc<io::Error>),
+    /// Writing to a `fmt::Write` failed.
+    Fmt(fmt::Error),
+    /// A value of the given kind is not supported at its position, with the
+    /// key it would have been serialized under, if known, such as
+    /// `filters.date_range`.
+    Unsupported {
+        position: Position,
+        kind: Kind,
+        path: Option<String>,
+    },
+}
+
+impl Error {
+    /// The key of the value that caused this error, if known.
+    pub fn path(&self) -> Option<&str> {
+        match *self {
+            Error::Unsupported { ref path, .. } => {
+                path.as_ref().map(|path| &**path)
+            },
+            _ => None,
+        }
+    }
+
+    /// The kind of the value that caused this error, if it was unsupported.
+    pub fn kind(&self) -> Option<Kind> {
+        match *self {
+            Error::Unsupported { kind, .. } => Some(kind),
+            _ => None,
+        }
+    }
+
+    /// Sets the path of this error, if it is not known yet.
+    fn at(mut self, key: &str) -> Self {
+        if let Error::Unsupported { ref mut path, .. } = self {
+            if path.is_none() {
+                *path = Some(key.to_owned());
+            }
+        }
+        self
+    }
 }
 
 impl fmt::Display for Error {
@@ -69,28 +562,153 @@ impl fmt::Display for Error {
             Error::Custom(ref msg) => msg.fmt(f),
             Error::InvalidValue(ref msg) => write!(f, "invalid value: {}", msg),
             Error::Utf8(ref err) => write!(f, "invalid UTF-8: {}", err),
+            Error::Io(ref err) => write!(f, "I/O error: {}", err),
+            Error::Fmt(ref err) => write!(f, "formatting error: {}", err),
+            Error::Unsupported { position, kind, ref path } => {
+                match position {
+                    Position::TopLevel => {
+                        write!(f, "unsupported top-level {}, expected a map, \
+                                   a struct or a sequence of pairs", kind)?
+                    },
+                    Position::Key => write!(f, "unsupported {} key", kind)?,
+                    Position::Pair => {
+                        write!(f, "unsupported {} pair, expected a tuple of \
+                                   a key and a value", kind)?
+                    },
+                    Position::Value => write!(f, "unsupported {} value", kind)?,
+                }
+                match *path {
+                    Some(ref path) => write!(f, " at `{}`", path),
+                    None => Ok(()),
+                }
+            },
         }
     }
 }
 
 impl error::Error for Error {
+    #[allow(deprecated)]
     fn description(&self) -> &str {
         match *self {
             Error::Custom(ref msg) => msg,
             Error::InvalidValue(ref msg) => msg,
             Error::Utf8(ref err) => error::Error::description(err),
+            Error::Io(ref err) => error::Error::description(&**err),
+            Error::Fmt(ref err) => error::Error::description(err),
+            Error::Unsupported { .. } => "unsupported value",
         }
     }
 
-    /// The lower-level cause of this error, in the case of a `Utf8` error.
-    fn cause(&self) -> Option<&error::Error> {
+    /// The lower-level cause of this error, in the case of a `Utf8`, `Io`
+    /// or `Fmt` error.
+    #[allow(deprecated)]
+    fn cause(&self) -> Option<&dyn error::Error> {
+        error::Error::source(self)
+    }
+
+    /// The lower-level source of this error, in the case of a `Utf8`, `Io`
+    /// or `Fmt` error.
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
-            Error::Custom(_) | Error::InvalidValue(_) => None,
+            Error::Custom(_) |
+            Error::InvalidValue(_) |
+            Error::Unsupported { .. } => None,
             Error::Utf8(ref err) => Some(err),
+            Error::Io(ref err) => Some(&**err),
+            Error::Fmt(ref err) => Some(err),
         }
     }
 }
 
+/// I/O errors are equal when they are of the same kind.
+impl PartialEq for Error {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Error::Custom(a), Error::Custom(b)) |
+            (Error::InvalidValue(a), Error::InvalidValue(b)) => a == b,
+            (Error::Utf8(a), Error::Utf8(b)) => a == b,
+            (Error::Io(a), Error::Io(b)) => a.kind() == b.kind(),
+            (Error::Fmt(a), Error::Fmt(b)) => a == b,
+            (Error::Unsupported { position, kind, path },
+             Error::Unsupported {
+                 position: other_position,
+                 kind: other_kind,
+                 path: other_path,
+             }) => {
+                position == other_position &&
+                    kind == other_kind &&
+                    path == other_path
+            },
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Error {}
+
+/// Where an unsupported value was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Position {
+    /// The value being serialized itself.
+    TopLevel,
+    /// The key of a pair or of a map entry.
+    Key,
+    /// An element of a top-level sequence, expected to be a pair.
+    Pair,
+    /// The value of a pair or of a field.
+    Value,
+}
+
+/// The kind of a value in the serde data model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    Bool,
+    Int,
+    Float,
+    Char,
+    Str,
+    Bytes,
+    Unit,
+    UnitStruct,
+    UnitVariant,
+    NewtypeStruct,
+    NewtypeVariant,
+    Option,
+    Seq,
+    Tuple,
+    TupleStruct,
+    TupleVariant,
+    Map,
+    Struct,
+    StructVariant,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        f.write_str(match *self {
+            Kind::Bool => "boolean",
+            Kind::Int => "integer",
+            Kind::Float => "float",
+            Kind::Char => "char",
+            Kind::Str => "string",
+            Kind::Bytes => "bytes",
+            Kind::Unit => "unit",
+            Kind::UnitStruct => "unit struct",
+            Kind::UnitVariant => "unit variant",
+            Kind::NewtypeStruct => "newtype struct",
+            Kind::NewtypeVariant => "newtype variant",
+            Kind::Option => "option",
+            Kind::Seq => "sequence",
+            Kind::Tuple => "tuple",
+            Kind::TupleStruct => "tuple struct",
+            Kind::TupleVariant => "tuple variant",
+            Kind::Map => "map",
+            Kind::Struct => "struct",
+            Kind::StructVariant => "struct variant",
+        })
+    }
+}
+
 impl ser::Error for Error {
     fn custom<T: Into<String>>(msg: T) -> Self {
         Error::Custom(msg.into().into())
@@ -136,8 +754,8 @@ pub struct StructVariantState {
     _state: (),
 }
 
-impl<'output, Target> ser::Serializer for Serializer<'output, Target>
-    where Target: 'output + UrlEncodedTarget
+impl<'output, S> ser::Serializer for Serializer<'output, S>
+    where S: 'output + Sink
 {
     type Error = Error;
 
@@ -164,104 +782,104 @@ impl<'output, Target> ser::Serializer for Serializer<'output, Target>
 
     /// Returns an error.
     fn serialize_bool(&mut self, _v: bool) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Bool))
     }
 
     /// Returns an error.
     fn serialize_isize(&mut self, _v: isize) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Int))
     }
 
     /// Returns an error.
     fn serialize_i8(&mut self, _v: i8) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Int))
     }
 
     /// Returns an error.
     fn serialize_i16(&mut self, _v: i16) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Int))
     }
 
     /// Returns an error.
     fn serialize_i32(&mut self, _v: i32) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Int))
     }
 
     /// Returns an error.
     fn serialize_i64(&mut self, _v: i64) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Int))
     }
 
     /// Returns an error.
     fn serialize_usize(&mut self, _v: usize) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Int))
     }
 
     /// Returns an error.
     fn serialize_u8(&mut self, _v: u8) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Int))
     }
 
     /// Returns an error.
     fn serialize_u16(&mut self, _v: u16) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Int))
     }
 
     /// Returns an error.
     fn serialize_u32(&mut self, _v: u32) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Int))
     }
 
     /// Returns an error.
     fn serialize_u64(&mut self, _v: u64) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Int))
     }
 
     /// Returns an error.
     fn serialize_f32(&mut self, _v: f32) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Float))
     }
 
     /// Returns an error.
     fn serialize_f64(&mut self, _v: f64) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Float))
     }
 
     /// Returns an error.
     fn serialize_char(&mut self, _v: char) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Char))
     }
 
     /// Returns an error.
     fn serialize_str(&mut self, _value: &str) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Str))
     }
 
     /// Returns an error.
     fn serialize_bytes(&mut self, _value: &[u8]) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Bytes))
     }
 
     /// Returns an error.
     fn serialize_unit(&mut self) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Unit))
     }
 
     /// Returns an error.
     fn serialize_unit_struct(
             &mut self, _name: &'static str)
             -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::UnitStruct))
     }
 
-    /// Returns an error.
+    /// Serializes the variant name under the tag key.
     fn serialize_unit_variant(
             &mut self,
             _name: &'static str,
             _variant_index: usize,
-            _variant: &'static str)
+            variant: &'static str)
             -> Result<(), Error> {
-        Err(Error::top_level())
+        sink::Sink::append_pair(self.sink, self.config.tag, variant)
     }
 
     /// Serializes the inner value, ignoring the newtype name.
@@ -273,17 +891,20 @@ impl<'output, Target> ser::Serializer for Serializer<'output, Target>
         value.serialize(self)
     }
 
-    /// Returns an error.
+    /// Serializes the variant name under the tag key, followed by the
+    /// inner value, writing nothing if the inner value is not supported.
     fn serialize_newtype_variant<T>(
             &mut self,                                    
             _name: &'static str,                                    
-            _variant_index: usize,                                    
-            _variant: &'static str,                                    
-            _value: T)                                    
+            _variant_index: usize,
+            variant: &'static str,
+            value: T)
             -> Result<(), Error>
         where T: ser::Serialize
     {
-        Err(Error::top_level())
+        let mut sink = TaggedSink::new(self.sink, self.config.tag, variant);
+        value.serialize(&mut Serializer::with_config(&mut sink, self.config))?;
+        sink.finish()
     }
 
     /// Returns an error.
@@ -311,7 +932,8 @@ impl<'output, Target> ser::Serializer for Serializer<'output, Target>
             -> Result<(), Error>
         where T: ser::Serialize
     {
-        value.serialize(&mut pair::PairSerializer::new(self.urlencoder))
+        value.serialize(
+            &mut pair::PairSerializer::new(self.sink, self.config))
     }
 
     /// Finishes serializing a sequence.
@@ -328,7 +950,7 @@ impl<'output, Target> ser::Serializer for Serializer<'output, Target>
 
     /// Returns an error.
     fn serialize_tuple(&mut self, _len: usize) -> Result<TupleState, Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Tuple))
     }
 
     /// Returns an error.
@@ -337,19 +959,19 @@ impl<'output, Target> ser::Serializer for Serializer<'output, Target>
             -> Result<(), Error>
         where T: ser::Serialize
     {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Tuple))
     }
 
     /// Returns an error.
     fn serialize_tuple_end(&mut self, _state: TupleState) -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::Tuple))
     }
 
     /// Returns an error.
     fn serialize_tuple_struct(
             &mut self, _name: &'static str, _len: usize)
             -> Result<TupleStructState, Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::TupleStruct))
     }
 
     /// Returns an error.
@@ -358,7 +980,7 @@ impl<'output, Target> ser::Serializer for Serializer<'output, Target>
             -> Result<(), Error>
         where T: ser::Serialize
     {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::TupleStruct))
     }
 
     /// Returns an error.
@@ -366,7 +988,7 @@ impl<'output, Target> ser::Serializer for Serializer<'output, Target>
             &mut self, _state: TupleStructState)
             -> Result<(), Error>
     {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::TupleStruct))
     }
 
     /// Returns an error.
@@ -377,7 +999,7 @@ impl<'output, Target> ser::Serializer for Serializer<'output, Target>
             _variant: &'static str,
             _len: usize)
             -> Result<TupleVariantState, Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::TupleVariant))
     }
 
     /// Returns an error.
@@ -386,14 +1008,14 @@ impl<'output, Target> ser::Serializer for Serializer<'output, Target>
             -> Result<(), Error>
         where T: ser::Serialize
     {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::TupleVariant))
     }
 
     /// Returns an error.
     fn serialize_tuple_variant_end(
             &mut self, _state: TupleVariantState)
             -> Result<(), Error> {
-        Err(Error::top_level())
+        Err(Error::top_level(Kind::TupleVariant))
     }
 
     /// Begins to serialize a map, given length (if any) is ignored.
@@ -418,8 +1040,8 @@ impl<'output, Target> ser::Serializer for Serializer<'output, Target>
             -> Result<(), Error>
         where T: ser::Serialize
     {
-        let mut value_serializer =
-            try!(value::ValueSerializer::new(&mut state.key, self.urlencoder));
+        let mut value_serializer = value::ValueSerializer::new(
+            &mut state.key, self.sink, self.config)?;
         value.serialize(&mut value_serializer)
     }
 
@@ -445,8 +1067,8 @@ impl<'output, Target> ser::Serializer for Serializer<'output, Target>
         where T: ser::Serialize
     {
         let mut key = Some(key.into());
-        let mut value_serializer =
-            value::ValueSerializer::new(&mut key, self.urlencoder).unwrap();
+        let mut value_serializer = value::ValueSerializer::new(
+            &mut key, self.sink, self.config).unwrap();
         value.serialize(&mut value_serializer)
     }
 
@@ -456,38 +1078,43 @@ impl<'output, Target> ser::Serializer for Serializer<'output, Target>
         Ok(())
     }
 
-    /// Returns an error.
+    /// Serializes the variant name under the tag key, before the fields.
     fn serialize_struct_variant(
             &mut self,
             _name: &'static str,
             _variant_index: usize,
-            _variant: &'static str,
+            variant: &'static str,
             _len: usize)
             -> Result<StructVariantState, Error> {
-        Err(Error::top_level())
+        sink::Sink::append_pair(self.sink, self.config.tag, variant)?;
+        Ok(StructVariantState { _state: () })
     }
 
-    /// Returns an error.
+    /// Serializes a struct variant field.
     fn serialize_struct_variant_elt<T>(
             &mut self,
             _state: &mut StructVariantState,
-            _key: &'static str,
-            _value: T)
-            -> Result<(), Error> {
-        Err(Error::top_level())
+            key: &'static str,
+            value: T)
+            -> Result<(), Error>
+        where T: ser::Serialize
+    {
+        let mut key = Some(key.into());
+        let mut value_serializer = value::ValueSerializer::new(
+            &mut key, self.sink, self.config)?;
+        value.serialize(&mut value_serializer)
     }
 
-    /// Returns an error.
+    /// Finishes serializing a struct variant.
     fn serialize_struct_variant_end(
             &mut self, _state: StructVariantState)
             -> Result<(), Error> {
-        Err(Error::top_level())
+        Ok(())
     }
 }
 
 impl Error {
-    fn top_level() -> Self {
-        Error::Custom(
-            "top-level serializer supports only maps and structs".into())
+    fn top_level(kind: Kind) -> Self {
+        Error::Unsupported { position: Position::TopLevel, kind, path: None }
     }
 }
@@ -1,27 +1,28 @@
-use ser::{Error, key, value};
+use ser::{Config, Error, Kind, Position, key, value};
+use ser::sink::Sink;
 use serde::{Serialize, Serializer};
 use std::borrow::Cow;
-use url::form_urlencoded;
 
-pub struct PairSerializer<'target, Target>(
-        &'target mut form_urlencoded::Serializer<Target>)
-    where Target: 'target + form_urlencoded::Target;
+pub struct PairSerializer<'target, S>
+    where S: 'target + Sink
+{
+    sink: &'target mut S,
+    config: Config,
+}
 
-impl<'target, Target> PairSerializer<'target, Target>
-    where Target: 'target + form_urlencoded::Target
+impl<'target, S> PairSerializer<'target, S>
+    where S: 'target + Sink
 {
-    pub fn new(
-            serializer: &'target mut form_urlencoded::Serializer<Target>)
-            -> Self {
-        PairSerializer(serializer)
+    pub fn new(sink: &'target mut S, config: Config) -> Self {
+        PairSerializer { sink, config }
     }
 }
 
 pub struct TupleState(Option<Option<Cow<'static, str>>>);
 pub struct TupleStructState(TupleState);
 
-impl<'target, Target> Serializer for PairSerializer<'target, Target>
-    where Target: 'target + form_urlencoded::Target
+impl<'target, S> Serializer for PairSerializer<'target, S>
+    where S: 'target + Sink
 {
     type Error = Error;
     type SeqState = ();
@@ -33,77 +34,77 @@ impl<'target, Target> Serializer for PairSerializer<'target, Target>
     type StructVariantState = ();
 
     fn serialize_bool(&mut self, _v: bool) -> Result<(), Error> {
-        Err(Error::Custom("booleans are not supported values".into()))
+        Err(Error::unsupported_pair(Kind::Bool))
     }
 
     fn serialize_isize(&mut self, _v: isize) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Int))
     }
 
     fn serialize_i8(&mut self, _v: i8) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Int))
     }
 
     fn serialize_i16(&mut self, _v: i16) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Int))
     }
 
     fn serialize_i32(&mut self, _v: i32) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Int))
     }
 
     fn serialize_i64(&mut self, _v: i64) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Int))
     }
 
     fn serialize_usize(&mut self, _v: usize) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Int))
     }
 
     fn serialize_u8(&mut self, _v: u8) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Int))
     }
 
     fn serialize_u16(&mut self, _v: u16) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Int))
     }
 
     fn serialize_u32(&mut self, _v: u32) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Int))
     }
 
     fn serialize_u64(&mut self, _v: u64) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Int))
     }
 
     fn serialize_f32(&mut self, _v: f32) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Float))
     }
 
     fn serialize_f64(&mut self, _v: f64) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Float))
     }
 
     fn serialize_char(&mut self, _v: char) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Char))
     }
 
     fn serialize_str(&mut self, _value: &str) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Str))
     }
 
     fn serialize_bytes(&mut self, _value: &[u8]) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Bytes))
     }
 
     fn serialize_unit(&mut self) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Unit))
     }
 
     fn serialize_unit_struct(
             &mut self, _name: &'static str)
             -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::UnitStruct))
     }
 
     fn serialize_unit_variant(
@@ -112,7 +113,7 @@ impl<'target, Target> Serializer for PairSerializer<'target, Target>
             _variant_index: usize,
             _variant: &'static str)
             -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::UnitVariant))
     }
 
     fn serialize_newtype_struct<T>(
@@ -132,7 +133,7 @@ impl<'target, Target> Serializer for PairSerializer<'target, Target>
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::NewtypeVariant))
     }
 
     fn serialize_none(&mut self) -> Result<(), Error> {
@@ -147,30 +148,30 @@ impl<'target, Target> Serializer for PairSerializer<'target, Target>
 
     fn serialize_seq(&mut self, _len: Option<usize>)
                      -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Seq))
     }
 
     fn serialize_seq_elt<T>(&mut self, _state: &mut (), _value: T)
                             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Seq))
     }
 
     fn serialize_seq_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Seq))
     }
 
     fn serialize_seq_fixed_size(&mut self, _size: usize)
                                 -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Seq))
     }
 
     fn serialize_tuple(&mut self, len: usize) -> Result<TupleState, Error> {
         if len == 2 {
             Ok(TupleState(None))
         } else {
-            Err(Error::unsupported_pair())
+            Err(Error::unsupported_pair(Kind::Tuple))
         }
     }
 
@@ -185,16 +186,16 @@ impl<'target, Target> Serializer for PairSerializer<'target, Target>
                 {
                     let mut key_serializer =
                         key::MapKeySerializer::new(&mut key);
-                    try!(value.serialize(&mut key_serializer));
+                    value.serialize(&mut key_serializer)?;
                 }
                 state.0 = Some(key);
                 Ok(())
             },
             Some(ref mut key) => {
                 {
-                    let mut value_serializer =
-                        value::ValueSerializer::new(key, &mut self.0).unwrap();
-                    try!(value.serialize(&mut value_serializer));
+                    let mut value_serializer = value::ValueSerializer::new(
+                        key, self.sink, self.config).unwrap();
+                    value.serialize(&mut value_serializer)?;
                 }
                 state.0 = Some(None);
                 Ok(())
@@ -233,7 +234,7 @@ impl<'target, Target> Serializer for PairSerializer<'target, Target>
         _variant: &'static str,
         _len: usize)
         -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::TupleVariant))
     }
 
     fn serialize_tuple_variant_elt<T>(
@@ -241,19 +242,19 @@ impl<'target, Target> Serializer for PairSerializer<'target, Target>
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::TupleVariant))
     }
 
     fn serialize_tuple_variant_end(
             &mut self, _state: ())
             -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::TupleVariant))
     }
 
     fn serialize_map(
             &mut self, _len: Option<usize>)
             -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Map))
     }
 
     fn serialize_map_key<T>(
@@ -261,7 +262,7 @@ impl<'target, Target> Serializer for PairSerializer<'target, Target>
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Map))
     }
 
     fn serialize_map_value<T>(
@@ -269,16 +270,16 @@ impl<'target, Target> Serializer for PairSerializer<'target, Target>
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Map))
     }
 
     fn serialize_map_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Map))
     }
 
     fn serialize_struct(&mut self, _name: &'static str, _len: usize)
                         -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Struct))
     }
 
     fn serialize_struct_elt<T>(
@@ -289,13 +290,13 @@ impl<'target, Target> Serializer for PairSerializer<'target, Target>
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Struct))
     }
 
     fn serialize_struct_end(
             &mut self, _state: ())
             -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::Struct))
     }
 
     fn serialize_struct_variant(
@@ -305,7 +306,7 @@ impl<'target, Target> Serializer for PairSerializer<'target, Target>
             _variant: &'static str,
             _len: usize)
             -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::StructVariant))
     }
     fn serialize_struct_variant_elt<T>(
             &mut self,
@@ -313,18 +314,18 @@ impl<'target, Target> Serializer for PairSerializer<'target, Target>
             _key: &'static str,
             _value: T)
             -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::StructVariant))
     }
 
     fn serialize_struct_variant_end(
             &mut self, _state: ())
             -> Result<(), Error> {
-        Err(Error::unsupported_pair())
+        Err(Error::unsupported_pair(Kind::StructVariant))
     }
 }
 
 impl Error {    
-    fn unsupported_pair() -> Self {
-        Error::Custom("unsupported pair".into())
+    fn unsupported_pair(kind: Kind) -> Self {
+        Error::Unsupported { position: Position::Pair, kind, path: None }
     }
 }
@@ -0,0 +1,204 @@
+use ser::{Config, Error};
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+use url::form_urlencoded;
+
+/// A destination for serialized pairs.
+pub trait Sink {
+    /// Appends a pair, given before encoding.
+    fn append_pair(&mut self, key: &str, value: &str) -> Result<(), Error>;
+
+    /// Appends the elements of a sequence serialized with
+    /// `ArrayStyle::Comma` as a single pair, given before encoding.
+    ///
+    /// By default, the elements are joined with commas into a single
+    /// value, and an element containing a comma is an error as it could
+    /// not be told apart from the others.
+    fn append_comma_list(&mut self, key: &str, values: &[String])
+                         -> Result<(), Error> {
+        if let Some(value) = values.iter().find(|value| value.contains(',')) {
+            return Err(Error::InvalidValue(
+                format!("`{}` contains a comma, at `{}`", value, key).into()));
+        }
+        self.append_pair(key, &values.join(","))
+    }
+}
+
+impl<Target> Sink for form_urlencoded::Serializer<Target>
+    where Target: form_urlencoded::Target
+{
+    fn append_pair(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        form_urlencoded::Serializer::append_pair(self, key, value);
+        Ok(())
+    }
+}
+
+/// Collects the values of sequences serialized with `ArrayStyle::Comma`.
+#[derive(Default)]
+pub struct Values(pub Vec<String>);
+
+impl Sink for Values {
+    fn append_pair(&mut self, _key: &str, value: &str) -> Result<(), Error> {
+        self.0.push(value.to_owned());
+        Ok(())
+    }
+}
+
+/// Gives pairs, unencoded, to an `Extend` collection.
+pub struct ExtendSink<'a, E: 'a>(pub &'a mut E);
+
+impl<'a, E> Sink for ExtendSink<'a, E>
+    where E: Extend<(String, String)>
+{
+    fn append_pair(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        self.0.extend(Some((key.to_owned(), value.to_owned())));
+        Ok(())
+    }
+}
+
+/// Holds back the tag pair of a newtype variant until its value gives a
+/// pair, so that nothing is written for a value that is not supported.
+pub struct TaggedSink<'a, S: 'a> {
+    sink: &'a mut S,
+    tag: Option<(&'static str, &'static str)>,
+}
+
+impl<'a, S: Sink> TaggedSink<'a, S> {
+    pub fn new(sink: &'a mut S, key: &'static str, variant: &'static str)
+               -> Self {
+        TaggedSink { sink, tag: Some((key, variant)) }
+    }
+
+    /// Appends the tag pair if the value gave no pair.
+    pub fn finish(mut self) -> Result<(), Error> {
+        self.append_tag()
+    }
+
+    fn append_tag(&mut self) -> Result<(), Error> {
+        match self.tag.take() {
+            Some((key, variant)) => self.sink.append_pair(key, variant),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'a, S: Sink> Sink for TaggedSink<'a, S> {
+    fn append_pair(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        self.append_tag()?;
+        self.sink.append_pair(key, value)
+    }
+
+    fn append_comma_list(&mut self, key: &str, values: &[String])
+                         -> Result<(), Error> {
+        self.append_tag()?;
+        self.sink.append_comma_list(key, values)
+    }
+}
+
+/// Encodes pairs and writes them to an `io::Write`, as they are appended,
+/// each with a single call to `write_all`.
+pub struct IoSink<W> {
+    writer: W,
+    buffer: String,
+    first: bool,
+    config: Config,
+}
+
+impl<W: io::Write> IoSink<W> {
+    pub fn new(writer: W, config: Config) -> Self {
+        IoSink { writer, buffer: String::new(), first: true, config }
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(|err| Error::Io(Arc::new(err)))
+    }
+
+    fn write<V: AsRef<str>>(&mut self, key: &str, values: &[V], list: bool)
+                            -> Result<(), Error> {
+        let buffer = &mut self.buffer;
+        buffer.clear();
+        write_pair(&mut self.first, self.config, key, values, list, |chunk| {
+            buffer.push_str(chunk);
+            Ok(())
+        })?;
+        self.writer.write_all(buffer.as_bytes())
+            .map_err(|err| Error::Io(Arc::new(err)))
+    }
+}
+
+impl<W: io::Write> Sink for IoSink<W> {
+    fn append_pair(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        self.write(key, &[value], false)
+    }
+
+    fn append_comma_list(&mut self, key: &str, values: &[String])
+                         -> Result<(), Error> {
+        self.write(key, values, true)
+    }
+}
+
+/// Encodes pairs and writes them to a `fmt::Write`, as they are appended.
+pub struct FmtSink<W> {
+    writer: W,
+    first: bool,
+    config: Config,
+}
+
+impl<W: fmt::Write> FmtSink<W> {
+    pub fn new(writer: W, config: Config) -> Self {
+        FmtSink { writer, first: true, config }
+    }
+
+    fn write<V: AsRef<str>>(&mut self, key: &str, values: &[V], list: bool)
+                            -> Result<(), Error> {
+        let writer = &mut self.writer;
+        write_pair(&mut self.first, self.config, key, values, list, |chunk| {
+            writer.write_str(chunk).map_err(Error::Fmt)
+        })
+    }
+}
+
+impl<W: fmt::Write> Sink for FmtSink<W> {
+    fn append_pair(&mut self, key: &str, value: &str) -> Result<(), Error> {
+        self.write(key, &[value], false)
+    }
+
+    fn append_comma_list(&mut self, key: &str, values: &[String])
+                         -> Result<(), Error> {
+        self.write(key, values, true)
+    }
+}
+
+/// Writes an encoded pair in chunks, preceded by a separator unless it is
+/// the first one.
+///
+/// The values are joined with unescaped commas, escaping the ones they
+/// contain, if they are the elements of a comma-separated sequence.
+fn write_pair<F, V>(first: &mut bool, config: Config, key: &str,
+                    values: &[V], list: bool, mut write: F)
+                    -> Result<(), Error>
+    where F: FnMut(&str) -> Result<(), Error>,
+          V: AsRef<str>,
+{
+    let separators = [config.pair_separator, config.key_value_separator];
+    let mut buf = [0; 4];
+    if !*first {
+        write(char::from(config.pair_separator).encode_utf8(&mut buf))?;
+    }
+    *first = false;
+    config.encode_set.encode(
+        &config.charset.encode(key), &separators, &mut write)?;
+    write(char::from(config.key_value_separator).encode_utf8(&mut buf))?;
+    let escaped = [config.pair_separator, config.key_value_separator, b','];
+    let escaped = if list { &escaped[..] } else { &separators[..] };
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            write(",")?;
+        }
+        config.encode_set.encode(
+            &config.charset.encode(value.as_ref()), escaped, &mut write)?;
+    }
+    Ok(())
+}
@@ -1,27 +1,30 @@
-use ser::Error;
+use ser::{ArrayStyle, Config, Error, Kind, MapState, Nesting, Position, key};
+use ser::sink::{Sink, Values};
 use serde::{Serialize, Serializer};
 use std::borrow::Cow;
 use std::str;
-use url::form_urlencoded;
 
-pub struct ValueSerializer<'key, 'target, Target>
-    where Target: 'target + form_urlencoded::Target
+pub struct ValueSerializer<'key, 'target, S>
+    where S: 'target + Sink
 {
     key: &'key mut Option<Cow<'static, str>>,
-    serializer: &'target mut form_urlencoded::Serializer<Target>
+    sink: &'target mut S,
+    config: Config,
 }
 
-impl<'key, 'target, Target> ValueSerializer<'key, 'target, Target>
-    where Target: 'target + form_urlencoded::Target
+impl<'key, 'target, S> ValueSerializer<'key, 'target, S>
+    where S: 'target + Sink
 {
     pub fn new(
             key: &'key mut Option<Cow<'static, str>>,
-            serializer: &'target mut form_urlencoded::Serializer<Target>)
+            sink: &'target mut S,
+            config: Config)
             -> Result<Self, Error> {
         if key.is_some() {
             Ok(ValueSerializer {
-                key: key,
-                serializer: serializer,
+                key,
+                sink,
+                config,
             })
         } else {
             Err(Error::no_key())
@@ -30,24 +33,133 @@ impl<'key, 'target, Target> ValueSerializer<'key, 'target, Target>
 
     fn append_pair(&mut self, value: &str) -> Result<(), Error> {
         if let Some(key) = self.key.take() {
-            self.serializer.append_pair(&key, value);
+            self.sink.append_pair(&key, value)
+        } else {
+            Err(Error::no_key())
+        }
+    }
+
+    fn begin_seq(&mut self) -> Result<SeqState, Error> {
+        if self.key.is_some() {
+            Ok(SeqState {
+                index: 0,
+                values: Values::default(),
+            })
+        } else {
+            Err(Error::no_key())
+        }
+    }
+
+    /// Serializes a sequence element according to the configured
+    /// `ArrayStyle`.
+    fn serialize_elt<T>(&mut self, state: &mut SeqState, value: T)
+                        -> Result<(), Error>
+        where T: Serialize
+    {
+        let mut key = match self.key.as_ref() {
+            Some(key) => Some(match self.config.array_style {
+                ArrayStyle::Repeat | ArrayStyle::Comma => key.clone(),
+                ArrayStyle::Brackets => format!("{}[]", key).into(),
+                ArrayStyle::Indices => {
+                    format!("{}[{}]", key, state.index).into()
+                },
+            }),
+            None => return Err(Error::no_key()),
+        };
+        state.index += 1;
+        // Only indexed elements may be maps or structs, as the fields of
+        // different elements could not be told apart otherwise.
+        let config = match self.config.array_style {
+            ArrayStyle::Indices => self.config,
+            _ => self.config.nesting(Nesting::Disabled),
+        };
+        if config.array_style == ArrayStyle::Comma {
+            let mut value_serializer = ValueSerializer::new(
+                &mut key, &mut state.values, config)?;
+            return value.serialize(&mut value_serializer);
+        }
+        let mut value_serializer =
+            ValueSerializer::new(&mut key, self.sink, config)?;
+        value.serialize(&mut value_serializer)
+    }
+
+    fn begin_nested(&mut self, kind: Kind) -> Result<(), Error> {
+        match (self.key.is_some(), self.config.nesting) {
+            (false, _) => Err(Error::no_key()),
+            (true, Nesting::Disabled) => Err(self.unsupported(kind)),
+            (true, Nesting::Brackets) | (true, Nesting::Dots) => Ok(()),
+        }
+    }
+
+    /// Returns an error for an unsupported value, at the current key.
+    fn unsupported(&self, kind: Kind) -> Error {
+        let error = Error::unsupported_value(kind);
+        match *self.key {
+            Some(ref key) => error.at(key),
+            None => error,
+        }
+    }
+
+    /// Serializes a field of a nested map or struct, composing its key
+    /// with the key of the value according to the configured `Nesting`,
+    /// which `begin_nested` checked is enabled.
+    fn serialize_nested<T>(&mut self, field: &str, value: T)
+                           -> Result<(), Error>
+        where T: Serialize
+    {
+        let mut key = match (self.key.as_ref(), self.config.nesting) {
+            (None, _) => return Err(Error::no_key()),
+            (Some(key), Nesting::Disabled) |
+            (Some(key), Nesting::Brackets) => {
+                Some(format!("{}[{}]", key, field).into())
+            },
+            (Some(key), Nesting::Dots) => {
+                Some(format!("{}.{}", key, field).into())
+            },
+        };
+        let mut value_serializer =
+            ValueSerializer::new(&mut key, self.sink, self.config)?;
+        value.serialize(&mut value_serializer)
+    }
+
+    fn end_nested(&mut self) -> Result<(), Error> {
+        if self.key.take().is_some() {
             Ok(())
         } else {
             Err(Error::no_key())
         }
     }
+
+    fn end_seq(&mut self, state: SeqState) -> Result<(), Error> {
+        if state.values.0.is_empty() {
+            return match self.key.take() {
+                Some(_) => Ok(()),
+                None => Err(Error::no_key()),
+            };
+        }
+        if let Some(key) = self.key.take() {
+            self.sink.append_comma_list(&key, &state.values.0)
+        } else {
+            Err(Error::no_key())
+        }
+    }
 }
 
-impl<'key, 'target, Target> Serializer
-    for ValueSerializer<'key, 'target, Target>
-    where Target: 'target + form_urlencoded::Target
+/// State used when serializing sequences, tuples and tuple structs.
+pub struct SeqState {
+    index: usize,
+    values: Values,
+}
+
+impl<'key, 'target, S> Serializer for ValueSerializer<'key, 'target, S>
+    where S: 'target + Sink
 {
     type Error = Error;
-    type SeqState = ();
-    type TupleState = ();
-    type TupleStructState = ();
+    type SeqState = SeqState;
+    type TupleState = SeqState;
+    type TupleStructState = SeqState;
     type TupleVariantState = ();
-    type MapState = ();
+    type MapState = MapState;
     type StructState = ();
     type StructVariantState = ();
 
@@ -119,7 +231,7 @@ impl<'key, 'target, Target> Serializer
     }
 
     fn serialize_unit(&mut self) -> Result<(), Error> {
-        Err(Error::unsupported_value())
+        Err(self.unsupported(Kind::Unit))
     }
 
     fn serialize_unit_struct(
@@ -154,11 +266,11 @@ impl<'key, 'target, Target> Serializer
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_value())
+        Err(self.unsupported(Kind::NewtypeVariant))
     }
 
     fn serialize_none(&mut self) -> Result<(), Error> {
-        if let Some(_) = self.key.take() {
+        if self.key.take().is_some() {
             Ok(())
         } else {
             Err(Error::no_key())
@@ -171,58 +283,61 @@ impl<'key, 'target, Target> Serializer
         value.serialize(self)
     }
 
-    fn serialize_seq(&mut self, _len: Option<usize>) -> Result<(), Error> {
-        Err(Error::unsupported_value())
+    fn serialize_seq(&mut self, _len: Option<usize>)
+                     -> Result<SeqState, Error> {
+        self.begin_seq()
     }
 
     fn serialize_seq_elt<T>(
-            &mut self, _state: &mut (), _value: T)
+            &mut self, state: &mut SeqState, value: T)
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_value())
+        self.serialize_elt(state, value)
     }
 
-    fn serialize_seq_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_value())
+    fn serialize_seq_end(&mut self, state: SeqState) -> Result<(), Error> {
+        self.end_seq(state)
     }
 
-    fn serialize_seq_fixed_size(&mut self, _size: usize) -> Result<(), Error> {
-        Err(Error::unsupported_value())
+    fn serialize_seq_fixed_size(&mut self, _size: usize)
+                                -> Result<SeqState, Error> {
+        self.begin_seq()
     }
 
-    fn serialize_tuple(&mut self, _len: usize) -> Result<(), Error> {
-        Err(Error::unsupported_value())
+    fn serialize_tuple(&mut self, _len: usize) -> Result<SeqState, Error> {
+        self.begin_seq()
     }
 
     fn serialize_tuple_elt<T>(
-            &mut self, _state: &mut (), _value: T)
+            &mut self, state: &mut SeqState, value: T)
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_value())
+        self.serialize_elt(state, value)
     }
 
-    fn serialize_tuple_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_value())
+    fn serialize_tuple_end(&mut self, state: SeqState) -> Result<(), Error> {
+        self.end_seq(state)
     }
 
     fn serialize_tuple_struct(
             &mut self, _name: &'static str, _len: usize)
-            -> Result<(), Error> {
-        Err(Error::unsupported_value())
+            -> Result<SeqState, Error> {
+        self.begin_seq()
     }
 
     fn serialize_tuple_struct_elt<T>(
-            &mut self, _state: &mut (), _value: T)
+            &mut self, state: &mut SeqState, value: T)
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_value())
+        self.serialize_elt(state, value)
     }
 
-    fn serialize_tuple_struct_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_value())
+    fn serialize_tuple_struct_end(&mut self, state: SeqState)
+                                  -> Result<(), Error> {
+        self.end_seq(state)
     }
 
     fn serialize_tuple_variant(
@@ -232,7 +347,7 @@ impl<'key, 'target, Target> Serializer
         _variant: &'static str,
         _len: usize)
         -> Result<(), Error> {
-        Err(Error::unsupported_value())
+        Err(self.unsupported(Kind::TupleVariant))
     }
 
     fn serialize_tuple_variant_elt<T>(
@@ -240,56 +355,65 @@ impl<'key, 'target, Target> Serializer
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_value())
+        Err(self.unsupported(Kind::TupleVariant))
     }
 
     fn serialize_tuple_variant_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_value())
+        Err(self.unsupported(Kind::TupleVariant))
     }
 
-    fn serialize_map(&mut self, _len: Option<usize>) -> Result<(), Error> {
-        Err(Error::unsupported_value())
+    fn serialize_map(&mut self, _len: Option<usize>)
+                     -> Result<MapState, Error> {
+        self.begin_nested(Kind::Map)?;
+        Ok(MapState { key: None })
     }
 
     fn serialize_map_key<T>(
-            &mut self, _state: &mut (), _key: T)
+            &mut self, state: &mut MapState, key: T)
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_value())
+        key.serialize(&mut key::MapKeySerializer::new(&mut state.key))
+            .map_err(|err| match *self.key {
+                Some(ref key) => err.at(key),
+                None => err,
+            })
     }
 
     fn serialize_map_value<T>(
-            &mut self, _state: &mut (), _value: T)
+            &mut self, state: &mut MapState, value: T)
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_value())
+        match state.key.take() {
+            Some(field) => self.serialize_nested(&field, value),
+            None => Err(Error::no_key()),
+        }
     }
 
-    fn serialize_map_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_value())
+    fn serialize_map_end(&mut self, _state: MapState) -> Result<(), Error> {
+        self.end_nested()
     }
 
     fn serialize_struct(
             &mut self, _name: &'static str, _len: usize)
             -> Result<(), Error> {
-        Err(Error::unsupported_value())
+        self.begin_nested(Kind::Struct)
     }
 
     fn serialize_struct_elt<T>(
             &mut self,
             _state: &mut (),
-            _key: &'static str,
-            _value: T)
+            key: &'static str,
+            value: T)
             -> Result<(), Error>
         where T: Serialize
     {
-        Err(Error::unsupported_value())
+        self.serialize_nested(key, value)
     }
 
     fn serialize_struct_end(&mut self, _state: ()) -> Result<(), Error> {
-        Err(Error::unsupported_value())
+        self.end_nested()
     }
 
     fn serialize_struct_variant(
@@ -299,18 +423,18 @@ impl<'key, 'target, Target> Serializer
             _variant: &'static str,
             _len: usize)
             -> Result<(), Error> {
-        Err(Error::unsupported_value())
+        Err(self.unsupported(Kind::StructVariant))
     }
     fn serialize_struct_variant_elt<T>(
             &mut self, _state: &mut (), _key: &'static str, _value: T)
             -> Result<(), Error> {
-        Err(Error::unsupported_value())
+        Err(self.unsupported(Kind::StructVariant))
     }
 
     fn serialize_struct_variant_end(
             &mut self, _state: ())
             -> Result<(), Error> {
-        Err(Error::unsupported_value())
+        Err(self.unsupported(Kind::StructVariant))
     }
 }
 
@@ -320,7 +444,7 @@ impl Error {
             "tried to serialize a value before serializing key".into())
     }
 
-    fn unsupported_value() -> Self {
-        Error::Custom("unsupported value".into())
+    fn unsupported_value(kind: Kind) -> Self {
+        Error::Unsupported { position: Position::Value, kind, path: None }
     }
 }
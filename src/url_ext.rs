@@ -0,0 +1,63 @@
+//! Integration with `url::Url`.
+
+use de;
+use ser;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Extension methods to build and read the query of a `Url` from and into
+/// serializable values.
+///
+/// ```
+/// extern crate url;
+/// extern crate serde_urlencoded;
+///
+/// use serde_urlencoded::UrlExt;
+/// use std::collections::HashMap;
+/// use url::Url;
+///
+/// # fn main() {
+/// let mut url = Url::parse("https://example.com/search").unwrap();
+/// url.set_query_from(&[("q", "rust serde"), ("page", "2")]).unwrap();
+/// assert_eq!(url.as_str(), "https://example.com/search?q=rust+serde&page=2");
+///
+/// let params: HashMap<String, String> = url.query_as().unwrap();
+/// assert_eq!(params["q"], "rust serde");
+/// # }
+/// ```
+pub trait UrlExt {
+    /// Appends the pairs of a value to the query, leaving the URL unchanged
+    /// if serializing fails.
+    fn append_query_pairs<T: Serialize>(&mut self, input: &T)
+                                        -> Result<(), ser::Error>;
+
+    /// Replaces the query with the pairs of a value, removing it if there
+    /// are none.
+    fn set_query_from<T: Serialize>(&mut self, input: &T)
+                                    -> Result<(), ser::Error>;
+
+    /// Deserializes the query, treating a missing one as empty.
+    fn query_as<T: Deserialize>(&self) -> Result<T, de::Error>;
+}
+
+impl UrlExt for Url {
+    fn append_query_pairs<T: Serialize>(&mut self, input: &T)
+                                        -> Result<(), ser::Error> {
+        let pairs = ser::to_pairs(input)?;
+        if !pairs.is_empty() {
+            self.query_pairs_mut().extend_pairs(pairs);
+        }
+        Ok(())
+    }
+
+    fn set_query_from<T: Serialize>(&mut self, input: &T)
+                                    -> Result<(), ser::Error> {
+        let query = ser::to_string(input)?;
+        self.set_query(if query.is_empty() { None } else { Some(&query) });
+        Ok(())
+    }
+
+    fn query_as<T: Deserialize>(&self) -> Result<T, de::Error> {
+        de::from_str(self.query().unwrap_or(""))
+    }
+}
@@ -0,0 +1,968 @@
+extern crate serde;
+extern crate serde_json;
+extern crate serde_urlencoded;
+
+use serde::de::{self, Deserialize, Deserializer};
+use serde_urlencoded::de::{Config, Decoding, DuplicateKeys, EmptyValue, Error};
+use serde_urlencoded::de::{Limits, Nesting};
+use serde_urlencoded::ser;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+
+#[derive(Debug, PartialEq)]
+enum Status {
+    Active,
+    Inactive,
+}
+
+impl Deserialize for Status {
+    fn deserialize<D: Deserializer>(deserializer: &mut D)
+                                    -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl de::EnumVisitor for Visitor {
+            type Value = Status;
+
+            fn visit<V: de::VariantVisitor>(&mut self, mut visitor: V)
+                                            -> Result<Status, V::Error> {
+                let variant: String = visitor.visit_variant()?;
+                visitor.visit_unit()?;
+                match &*variant {
+                    "active" => Ok(Status::Active),
+                    "inactive" => Ok(Status::Inactive),
+                    _ => Err(de::Error::unknown_variant(&variant)),
+                }
+            }
+        }
+
+        deserializer.deserialize_enum(
+            "Status", &["active", "inactive"], Visitor)
+    }
+}
+
+/// An identifier given either as a number or as a name, like an untagged
+/// enum.
+#[derive(Debug, PartialEq)]
+enum Id {
+    Number(u64),
+    Name(String),
+}
+
+impl Deserialize for Id {
+    fn deserialize<D: Deserializer>(deserializer: &mut D)
+                                    -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl de::Visitor for Visitor {
+            type Value = Id;
+
+            fn visit_u64<E: de::Error>(&mut self, value: u64)
+                                       -> Result<Id, E> {
+                Ok(Id::Number(value))
+            }
+
+            fn visit_str<E: de::Error>(&mut self, value: &str)
+                                       -> Result<Id, E> {
+                Ok(Id::Name(value.to_owned()))
+            }
+        }
+
+        deserializer.deserialize(Visitor)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Person {
+    name: String,
+    age: Option<u32>,
+}
+
+impl Deserialize for Person {
+    fn deserialize<D: Deserializer>(deserializer: &mut D)
+                                    -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl de::Visitor for Visitor {
+            type Value = Person;
+
+            fn visit_map<V: de::MapVisitor>(&mut self, mut visitor: V)
+                                            -> Result<Person, V::Error> {
+                let mut name = None;
+                let mut age = None;
+                while let Some(key) = visitor.visit_key::<String>()? {
+                    match &*key {
+                        "name" => name = Some(visitor.visit_value()?),
+                        "age" => age = Some(visitor.visit_value()?),
+                        _ => {
+                            visitor.visit_value::<de::impls::IgnoredAny>()?;
+                        },
+                    }
+                }
+                visitor.end()?;
+                Ok(Person {
+                    name: match name {
+                        Some(name) => name,
+                        None => visitor.missing_field("name")?,
+                    },
+                    age: match age {
+                        Some(age) => age,
+                        None => visitor.missing_field("age")?,
+                    },
+                })
+            }
+        }
+
+        deserializer.deserialize_struct("Person", &["name", "age"], Visitor)
+    }
+}
+
+#[test]
+fn deserialize_map_int() {
+    let result = vec![
+        ("first".to_owned(), 23),
+        ("last".to_owned(), -42),
+    ];
+
+    assert_eq!(
+        serde_urlencoded::from_str("first=23&last=-42"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_map_bool() {
+    let result = vec![
+        ("one".to_owned(), true),
+        ("two".to_owned(), false),
+    ];
+
+    assert_eq!(
+        serde_urlencoded::from_str("one=true&two=false"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_map_float() {
+    let result = vec![
+        ("ratio".to_owned(), 0.5),
+        ("scale".to_owned(), 1e3),
+    ];
+
+    assert_eq!(
+        serde_urlencoded::from_str("ratio=0.5&scale=1e3"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_map_char() {
+    let result = vec![
+        ("initial".to_owned(), 'x'),
+    ];
+
+    assert_eq!(
+        serde_urlencoded::from_str("initial=x"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_invalid_int_names_key() {
+    let err = serde_urlencoded::from_str::<Vec<(String, u32)>>("page=two")
+        .unwrap_err();
+
+    assert!(err.to_string().contains("`page`"));
+}
+
+#[test]
+fn deserialize_repeated_keys_into_seq() {
+    let mut result = HashMap::new();
+    result.insert("tag".to_owned(), vec!["a".to_owned(), "b".to_owned()]);
+    result.insert("page".to_owned(), vec!["2".to_owned()]);
+
+    assert_eq!(
+        serde_urlencoded::from_str("tag=a&page=2&tag=b"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_repeated_keys_into_scalar() {
+    let mut result = HashMap::new();
+    result.insert("page".to_owned(), 3);
+
+    assert_eq!(
+        serde_urlencoded::from_str("page=2&page=3"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_repeated_keys_into_pairs() {
+    let result = vec![
+        ("tag".to_owned(), "a".to_owned()),
+        ("page".to_owned(), "2".to_owned()),
+        ("tag".to_owned(), "b".to_owned()),
+    ];
+
+    assert_eq!(
+        serde_urlencoded::from_str("tag=a&page=2&tag=b"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_duplicate_keys_first() {
+    let result = Person { name: "Ada".to_owned(), age: Some(36) };
+
+    assert_eq!(
+        Config::new()
+            .duplicate_keys(DuplicateKeys::First)
+            .from_str("name=Ada&age=36&name=Eve"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_duplicate_keys_last_into_seq() {
+    let mut result = HashMap::new();
+    result.insert("role".to_owned(), vec!["admin".to_owned()]);
+
+    assert_eq!(
+        Config::new()
+            .duplicate_keys(DuplicateKeys::Last)
+            .from_str("role=user&role=admin"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_duplicate_keys_error() {
+    assert_eq!(
+        Config::new()
+            .duplicate_keys(DuplicateKeys::Error)
+            .from_str::<Person>("name=Ada&name=Eve"),
+        Err(Error::DuplicateKey { key: "name".to_owned() }));
+}
+
+#[test]
+fn deserialize_duplicate_nested_keys_error() {
+    assert_eq!(
+        Config::new()
+            .nesting(Nesting::Brackets)
+            .duplicate_keys(DuplicateKeys::Error)
+            .from_str::<BTreeMap<String, Person>>(
+                "user[name]=Ada&user[name]=Eve"),
+        Err(Error::DuplicateKey { key: "user[name]".to_owned() }));
+}
+
+#[test]
+fn deserialize_duplicate_keys_error_allows_brackets() {
+    let mut result = HashMap::new();
+    result.insert("ids".to_owned(), vec![1, 2]);
+
+    assert_eq!(
+        Config::new()
+            .array_brackets(true)
+            .duplicate_keys(DuplicateKeys::Error)
+            .from_str("ids[]=1&ids[]=2"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_array_brackets() {
+    let mut result = HashMap::new();
+    result.insert("ids".to_owned(), vec![1, 2]);
+
+    assert_eq!(
+        Config::new().array_brackets(true).from_str("ids%5B%5D=1&ids[]=2"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_array_indices() {
+    let mut result = HashMap::new();
+    result.insert("ids".to_owned(), vec![1, 2, 3]);
+
+    assert_eq!(
+        Config::new().array_brackets(true).from_str(
+            "ids[2]=3&ids[0]=1&ids[1]=2"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_array_brackets_disabled() {
+    let mut result = HashMap::new();
+    result.insert("ids[]".to_owned(), vec![1, 2]);
+
+    assert_eq!(
+        serde_urlencoded::from_str("ids[]=1&ids[]=2"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_nested_brackets() {
+    let mut address = BTreeMap::new();
+    address.insert("city".to_owned(), "London".to_owned());
+    let mut user = BTreeMap::new();
+    user.insert("address".to_owned(), address);
+    let mut result = BTreeMap::new();
+    result.insert("user".to_owned(), user);
+
+    assert_eq!(
+        Config::new()
+            .nesting(Nesting::Brackets)
+            .from_str("user[address][city]=London"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_nested_seq_of_maps() {
+    let mut first = BTreeMap::new();
+    first.insert("name".to_owned(), "a".to_owned());
+    let mut second = BTreeMap::new();
+    second.insert("name".to_owned(), "b".to_owned());
+    let mut result = BTreeMap::new();
+    result.insert("items".to_owned(), vec![first, second]);
+
+    assert_eq!(
+        Config::new()
+            .nesting(Nesting::Brackets)
+            .from_str("items[1][name]=b&items[0][name]=a"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_unknown_nested_keys_ignored() {
+    assert_eq!(
+        Config::new()
+            .array_brackets(true)
+            .from_str("name=Ada&utm[0]=1"),
+        Ok(Person { name: "Ada".to_owned(), age: None }));
+    assert_eq!(
+        Config::new()
+            .nesting(Nesting::Brackets)
+            .from_str("extra[x]=1&name=Ada&extra[y][0]=2"),
+        Ok(Person { name: "Ada".to_owned(), age: None }));
+    assert_eq!(
+        Config::new().nesting(Nesting::Dots).from_str("meta.trace=1&name=Ada"),
+        Ok(Person { name: "Ada".to_owned(), age: None }));
+}
+
+#[test]
+fn deserialize_nested_dots() {
+    let mut owner = BTreeMap::new();
+    owner.insert("id".to_owned(), "7".to_owned());
+    let mut filter = BTreeMap::new();
+    filter.insert("owner".to_owned(), owner);
+    let mut result = BTreeMap::new();
+    result.insert("filter".to_owned(), filter);
+
+    assert_eq!(
+        Config::new()
+            .nesting(Nesting::Dots)
+            .from_str("filter.owner.id=7"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_dotted_array_brackets() {
+    let mut filter = BTreeMap::new();
+    filter.insert("ids".to_owned(), vec![1, 2]);
+    filter.insert("tags".to_owned(), vec![3, 4]);
+    let mut result = BTreeMap::new();
+    result.insert("filter".to_owned(), filter);
+
+    assert_eq!(
+        Config::new()
+            .nesting(Nesting::Dots)
+            .array_brackets(true)
+            .from_str("filter.ids[]=1&filter.ids[]=2&\
+                       filter.tags[1]=4&filter.tags[0]=3"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_top_level_dotted_array_brackets() {
+    let mut result = HashMap::new();
+    result.insert("ids".to_owned(), vec![1, 2]);
+
+    assert_eq!(
+        Config::new()
+            .nesting(Nesting::Dots)
+            .array_brackets(true)
+            .from_str("ids[]=1&ids[]=2"),
+        Ok(result));
+}
+
+#[test]
+fn round_trip_dotted_indices() {
+    let mut filter = BTreeMap::new();
+    filter.insert("ids".to_owned(), vec![1, 2]);
+    let mut params = BTreeMap::new();
+    params.insert("f".to_owned(), filter);
+    let encoded = ser::Config::new()
+        .nesting(Nesting::Dots)
+        .array_style(ser::ArrayStyle::Indices)
+        .to_string(&params)
+        .unwrap();
+
+    assert_eq!(encoded, "f.ids%5B0%5D=1&f.ids%5B1%5D=2");
+    assert_eq!(
+        Config::new()
+            .nesting(Nesting::Dots)
+            .array_brackets(true)
+            .from_str(&encoded),
+        Ok(params));
+}
+
+#[test]
+fn deserialize_unit_variant_values() {
+    let result = vec![
+        ("first".to_owned(), Status::Active),
+        ("second".to_owned(), Status::Inactive),
+    ];
+
+    assert_eq!(
+        serde_urlencoded::from_str("first=active&second=inactive"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_tagged_newtype_variant() {
+    let mut card = BTreeMap::new();
+    card.insert("number".to_owned(), "4242".to_owned());
+    card.insert("cvc".to_owned(), "123".to_owned());
+
+    assert_eq!(
+        serde_urlencoded::from_str("number=4242&type=Ok&cvc=123"),
+        Ok(Ok::<_, String>(card)));
+}
+
+#[test]
+fn deserialize_tagged_variant_without_tag() {
+    let err = serde_urlencoded::from_str::<Result<BTreeMap<String, String>,
+                                                  String>>("number=4242")
+        .unwrap_err();
+
+    assert!(err.to_string().contains("type"));
+}
+
+#[test]
+fn deserialize_option_absent_key() {
+    let result = Person { name: "Ada".to_owned(), age: None };
+
+    assert_eq!(serde_urlencoded::from_str("name=Ada"), Ok(result));
+}
+
+#[test]
+fn deserialize_option_missing_required_field() {
+    let err = serde_urlencoded::from_str::<Person>("age=36").unwrap_err();
+
+    assert!(err.to_string().contains("name"));
+}
+
+#[test]
+fn deserialize_option_empty_value() {
+    let result = Person { name: "Ada".to_owned(), age: None };
+
+    assert_eq!(serde_urlencoded::from_str("name=Ada&age="), Ok(result));
+}
+
+#[test]
+fn deserialize_option_bare_key() {
+    let result = vec![
+        ("age".to_owned(), None),
+        ("height".to_owned(), Some(170)),
+    ];
+
+    assert_eq!(
+        serde_urlencoded::from_str::<Vec<(String, Option<u32>)>>(
+            "age&height=170"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_option_empty_value_as_some() {
+    let mut result = HashMap::new();
+    result.insert("nickname".to_owned(), Some("".to_owned()));
+
+    assert_eq!(
+        Config::new().empty_value(EmptyValue::Some).from_str("nickname="),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_option_empty_value_as_error() {
+    let err = Config::new()
+        .empty_value(EmptyValue::Error)
+        .from_str::<Person>("name=Ada&age=")
+        .unwrap_err();
+
+    assert!(err.to_string().contains("`age`"));
+}
+
+#[test]
+fn deserialize_option_of_sequence() {
+    let mut result = HashMap::new();
+    let tags = vec!["a".to_owned(), "b".to_owned()];
+    result.insert("tag".to_owned(), Some(tags));
+    result.insert("none".to_owned(), None);
+
+    assert_eq!(
+        serde_urlencoded::from_str::<HashMap<String, Option<Vec<String>>>>(
+            "tag=a&tag=b&none="),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_invalid_value_error() {
+    let err = serde_urlencoded::from_str::<Person>("name=Ada&age=old")
+        .unwrap_err();
+
+    assert_eq!(err.key(), Some("age"));
+    assert_eq!(err.value(), Some("old"));
+}
+
+#[test]
+fn deserialize_missing_field_error() {
+    let err = serde_urlencoded::from_str::<Person>("age=36").unwrap_err();
+
+    assert_eq!(err, Error::MissingField { key: "name".to_owned() });
+}
+
+#[test]
+fn deserialize_nested_error_key() {
+    let err = Config::new()
+        .nesting(Nesting::Brackets)
+        .from_str::<BTreeMap<String, Person>>("user[name]=Ada&user[age]=x")
+        .unwrap_err();
+
+    assert_eq!(err.key(), Some("user[age]"));
+}
+
+#[test]
+fn deserialize_nested_missing_field_error() {
+    let err = Config::new()
+        .nesting(Nesting::Dots)
+        .from_str::<BTreeMap<String, Person>>("user.age=36")
+        .unwrap_err();
+
+    assert_eq!(err, Error::MissingField { key: "user.name".to_owned() });
+}
+
+#[test]
+fn deserialize_newtype_variant_value_error() {
+    let err = serde_urlencoded::from_str::<HashMap<String, Result<u32, u32>>>(
+        "a=Ok").unwrap_err();
+
+    assert_eq!(err.key(), Some("a"));
+    assert_eq!(
+        err.to_string(),
+        "invalid type, expected a newtype variant for key `a`");
+}
+
+#[test]
+fn deserialize_unknown_variant_error() {
+    let err = serde_urlencoded::from_str::<HashMap<String, Status>>(
+        "status=paused").unwrap_err();
+
+    assert_eq!(
+        err,
+        Error::UnknownVariant {
+            key: Some("status".to_owned()),
+            variant: "paused".to_owned(),
+        });
+}
+
+/// A reader returning its input one byte at a time.
+struct ByteReader<'a>(&'a [u8]);
+
+impl<'a> io::Read for ByteReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.split_first() {
+            Some((&byte, rest)) if !buf.is_empty() => {
+                buf[0] = byte;
+                self.0 = rest;
+                Ok(1)
+            },
+            _ => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn deserialize_from_reader() {
+    let mut result = HashMap::new();
+    result.insert("tag".to_owned(), vec!["a b".to_owned(), "c".to_owned()]);
+    result.insert("city".to_owned(), vec!["Zürich".to_owned()]);
+
+    assert_eq!(
+        serde_urlencoded::from_reader(&b"tag=a+b&city=Z%C3%BCrich&tag=c"[..]),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_from_reader_split_chunks() {
+    let result = vec![
+        ("café".to_owned(), "crème brûlée".to_owned()),
+        ("empty".to_owned(), "".to_owned()),
+        ("last".to_owned(), "&=".to_owned()),
+    ];
+
+    assert_eq!(
+        serde_urlencoded::from_reader(ByteReader(
+            b"caf%C3%A9=cr%C3%A8me+br%C3%BBl%C3%A9e&&empty=&last=%26%3D")),
+        Ok(result));
+}
+
+struct BrokenReader;
+
+impl io::Read for BrokenReader {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))
+    }
+}
+
+#[test]
+fn deserialize_from_reader_error() {
+    let err = serde_urlencoded::from_reader::<_, HashMap<String, String>>(
+        BrokenReader).unwrap_err();
+
+    match err {
+        Error::Io(err) => {
+            assert_eq!(err.kind(), io::ErrorKind::ConnectionReset)
+        },
+        err => panic!("unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn deserialize_from_pairs() {
+    let pairs = vec![("name", "Ada"), ("age", "36")];
+
+    assert_eq!(
+        serde_urlencoded::from_pairs(pairs),
+        Ok(Person { name: "Ada".to_owned(), age: Some(36) }));
+}
+
+#[test]
+fn deserialize_from_owned_pairs_nested() {
+    let pairs = vec![
+        ("user[name]".to_owned(), "Ada".to_owned()),
+        ("user[age]".to_owned(), "".to_owned()),
+    ];
+    let mut result = BTreeMap::new();
+    result.insert(
+        "user".to_owned(), Person { name: "Ada".to_owned(), age: None });
+
+    assert_eq!(
+        Config::new().nesting(Nesting::Brackets).from_pairs(pairs),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_inferred_untagged() {
+    let result = vec![
+        ("a".to_owned(), Id::Number(42)),
+        ("b".to_owned(), Id::Name("forty-two".to_owned())),
+    ];
+
+    assert_eq!(
+        Config::new().infer_types(true).from_str("a=42&b=forty-two"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_untagged_without_inference() {
+    let result = vec![("a".to_owned(), Id::Name("42".to_owned()))];
+
+    assert_eq!(serde_urlencoded::from_str("a=42"), Ok(result));
+}
+
+#[test]
+fn deserialize_inferred_json_value() {
+    let value: serde_json::Value = Config::new()
+        .infer_types(true)
+        .nesting(Nesting::Brackets)
+        .from_str("n=-3&x=1.5&ok=true&s=inf&ids=1&ids=2&user[age]=36&\
+                   zip=01234&plus=%2B5&zero=0&lead=00.5&\
+                   id=123456789012345678901234&e=1e400")
+        .unwrap();
+
+    assert_eq!(
+        serde_json::to_string(&value).unwrap(),
+        concat!(
+            r#"{"e":"1e400","id":"123456789012345678901234","ids":[1,2],"#,
+            r#""lead":"00.5","n":-3,"ok":true,"plus":"+5","s":"inf","#,
+            r#""user":{"age":36},"x":1.5,"zero":0,"zip":"01234"}"#));
+}
+
+#[test]
+fn deserialize_inferred_string_target() {
+    let mut result = HashMap::new();
+    result.insert("zip".to_owned(), "01234".to_owned());
+
+    assert_eq!(
+        Config::new().infer_types(true).from_str("zip=01234"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_limit_value_length() {
+    let config = Config::new().limits(Limits::new().max_value_len(3));
+
+    assert_eq!(
+        config.from_str::<HashMap<String, String>>("a=abc&b=abcd"),
+        Err(Error::LimitExceeded {
+            key: Some("b".to_owned()),
+            limit: "value length",
+            max: 3,
+        }));
+}
+
+#[test]
+fn deserialize_limit_key_length() {
+    let config = Config::new().limits(Limits::new().max_key_len(4));
+
+    assert_eq!(
+        config.from_str::<HashMap<String, String>>("name=Ada&surname=L"),
+        Err(Error::LimitExceeded {
+            key: None,
+            limit: "key length",
+            max: 4,
+        }));
+}
+
+#[test]
+fn deserialize_limit_total_length() {
+    let config = Config::new().limits(Limits::new().max_total_len(8));
+
+    assert_eq!(
+        config.from_str::<Vec<(String, String)>>("a=1&b=2&c=3&d=4&e=5"),
+        Err(Error::LimitExceeded {
+            key: None,
+            limit: "total length",
+            max: 8,
+        }));
+}
+
+#[test]
+fn deserialize_limit_depth() {
+    let config = Config::new()
+        .nesting(Nesting::Brackets)
+        .limits(Limits::new().max_depth(1));
+
+    assert_eq!(
+        config.from_str::<BTreeMap<String, BTreeMap<String, String>>>(
+            "user[name]=Ada&user[address][city]=London"),
+        Err(Error::LimitExceeded {
+            key: Some("user[address][city]".to_owned()),
+            limit: "depth",
+            max: 1,
+        }));
+}
+
+#[test]
+fn deserialize_limit_default_depth() {
+    let config = Config::new().nesting(Nesting::Brackets);
+    let key = format!("a{}", "[x]".repeat(300_000));
+    let input = format!("{}=1", key);
+
+    assert_eq!(
+        config.from_str::<HashMap<String, String>>(&input),
+        Err(Error::LimitExceeded {
+            key: Some(key),
+            limit: "depth",
+            max: 32,
+        }));
+}
+
+#[test]
+fn deserialize_limit_array_index() {
+    let config = Config::new()
+        .array_brackets(true)
+        .limits(Limits::new().max_index(100));
+
+    assert_eq!(
+        config.from_str::<HashMap<String, Vec<u32>>>(
+            "ids[0]=1&ids[18446744073709551616]=2"),
+        Err(Error::LimitExceeded {
+            key: Some("ids[18446744073709551616]".to_owned()),
+            limit: "array index",
+            max: 100,
+        }));
+}
+
+#[test]
+fn deserialize_limit_unbounded_reader() {
+    let config = Config::new()
+        .limits(Limits::new().max_key_len(16).max_value_len(64));
+    let reader = io::Read::chain(&b"key="[..], io::repeat(b'a'));
+
+    assert_eq!(
+        config.from_reader::<_, HashMap<String, String>>(reader),
+        Err(Error::LimitExceeded {
+            key: Some("key".to_owned()),
+            limit: "value length",
+            max: 64,
+        }));
+}
+
+#[test]
+fn deserialize_lenient_malformed_escapes() {
+    let result = vec![("a%zz".to_owned(), "100%".to_owned())];
+
+    assert_eq!(serde_urlencoded::from_str("a%zz=100%"), Ok(result));
+}
+
+#[test]
+fn deserialize_strict_malformed_escape_in_key() {
+    assert_eq!(
+        Config::new()
+            .decoding(Decoding::Strict)
+            .from_str::<Vec<(String, String)>>("ok=%41&a%zz=1"),
+        Err(Error::InvalidEscape { key: None, offset: 8 }));
+}
+
+#[test]
+fn deserialize_strict_valid_escapes() {
+    let result = vec![("café".to_owned(), "a b&c".to_owned())];
+
+    assert_eq!(
+        Config::new()
+            .decoding(Decoding::Strict)
+            .from_str("caf%C3%A9=a+b%26c"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_strict_invalid_utf8() {
+    let err = Config::new()
+        .decoding(Decoding::Strict)
+        .from_str::<Vec<(String, String)>>("name=caf%E9")
+        .unwrap_err();
+
+    match err {
+        Error::Utf8 { key, error } => {
+            assert_eq!(key, Some("name".to_owned()));
+            assert_eq!(error.valid_up_to(), 3);
+        },
+        err => panic!("unexpected error: {}", err),
+    }
+    assert_eq!(
+        serde_urlencoded::from_str("name=caf%E9"),
+        Ok(vec![("name".to_owned(), "caf\u{FFFD}".to_owned())]));
+}
+
+#[test]
+fn deserialize_strict_from_reader_offset() {
+    assert_eq!(
+        Config::new()
+            .decoding(Decoding::Strict)
+            .from_reader::<_, HashMap<String, String>>(ByteReader(
+                b"name=Ada&&note=50%2")),
+        Err(Error::InvalidEscape {
+            key: Some("note".to_owned()),
+            offset: 17,
+        }));
+}
+
+#[test]
+fn deserialize_raw_decoding() {
+    let result = vec![("q".to_owned(), "a+b%20c%zz".to_owned())];
+
+    assert_eq!(
+        Config::new().decoding(Decoding::Raw).from_str("q=a+b%20c%zz"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_plus_not_as_space() {
+    let mut result = HashMap::new();
+    result.insert("a+b".to_owned(), "1+1 2".to_owned());
+
+    assert_eq!(
+        Config::new().plus_as_space(false).from_str("a+b=1+1%202"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_semicolon_separators() {
+    let result = vec![
+        ("a".to_owned(), "1".to_owned()),
+        ("b".to_owned(), "2&3".to_owned()),
+    ];
+
+    assert_eq!(
+        Config::new().pair_separators(b";").from_str("a=1;b=2&3"),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_custom_separators() {
+    let result = Person { name: "Ada".to_owned(), age: Some(36) };
+
+    assert_eq!(
+        Config::new()
+            .pair_separators(b"|")
+            .key_value_separator(b':')
+            .from_str("name:Ada||age:36"),
+        Ok(result));
+}
+
+#[test]
+#[should_panic(expected = "pair separators must not be empty")]
+fn deserialize_empty_separators() {
+    let _ = Config::new().pair_separators(b"");
+}
+
+#[test]
+#[should_panic(expected = "pair separators must be ASCII punctuation")]
+fn deserialize_hex_digit_separator() {
+    let _ = Config::new().pair_separators(b"&A");
+}
+
+#[test]
+#[should_panic(expected = "key/value separator must be ASCII punctuation")]
+fn deserialize_percent_separator() {
+    let _ = Config::new().key_value_separator(b'%');
+}
+
+#[test]
+fn deserialize_swapped_separators() {
+    let result = vec![("a".to_owned(), "1".to_owned())];
+
+    assert_eq!(
+        Config::new()
+            .key_value_separator(b'&')
+            .pair_separators(b"=")
+            .from_str("a&1="),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_conflicting_separators() {
+    let err = Error::Custom {
+        key: None,
+        message: "pair and key/value separators must differ".to_owned(),
+    };
+
+    assert_eq!(
+        Config::new()
+            .pair_separators(b"&=")
+            .from_str::<Vec<(String, String)>>("a=1"),
+        Err(err.clone()));
+    assert_eq!(
+        Config::new()
+            .pair_separators(b"&=")
+            .from_reader::<_, Vec<(String, String)>>(ByteReader(b"a=1")),
+        Err(err));
+}
+
+#[test]
+fn deserialize_reader_with_both_separators() {
+    let result = vec![
+        ("a".to_owned(), "1".to_owned()),
+        ("b".to_owned(), "2".to_owned()),
+        ("c".to_owned(), "3".to_owned()),
+    ];
+
+    assert_eq!(
+        Config::new()
+            .pair_separators(b"&;")
+            .from_reader(ByteReader(b"a=1;b=2&c=3")),
+        Ok(result));
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "query_encoding")]
+
+extern crate encoding;
+extern crate serde_urlencoded;
+
+use encoding::all::{WINDOWS_31J, UTF_8, WINDOWS_1252};
+use serde_urlencoded::{de, ser};
+use std::collections::HashMap;
+
+#[test]
+fn deserialize_windows_1252() {
+    let mut result = HashMap::new();
+    result.insert("price".to_owned(), "3€".to_owned());
+
+    assert_eq!(
+        serde_urlencoded::from_bytes_with_encoding(
+            b"price=3%80", WINDOWS_1252),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_shift_jis() {
+    let mut result = HashMap::new();
+    result.insert("名前".to_owned(), "山田".to_owned());
+
+    assert_eq!(
+        serde_urlencoded::from_bytes_with_encoding(
+            b"%96%BC%91O=%8ER%93c", WINDOWS_31J),
+        Ok(result));
+}
+
+#[test]
+fn deserialize_legacy_encoding_non_ascii_error() {
+    let err = de::Config::new()
+        .encoding(WINDOWS_1252)
+        .from_bytes::<HashMap<String, String>>(b"price=3\x80")
+        .unwrap_err();
+
+    assert_eq!(err, de::Error::NonAscii { key: None });
+    assert!(err.to_string().contains("percent-encoded"));
+}
+
+#[test]
+fn deserialize_detected_charset() {
+    let config = de::Config::new().encoding(UTF_8).detect_charset(true);
+
+    assert_eq!(
+        config.from_str::<HashMap<String, String>>(
+            "_charset_=windows-1252&cheese=comt%E9")
+            .unwrap()["cheese"],
+        "comté");
+}
+
+#[test]
+fn deserialize_detected_charset_non_ascii_error() {
+    let config = de::Config::new().detect_charset(true);
+
+    assert_eq!(
+        config.from_str::<HashMap<String, String>>(
+            "name=café&_charset_=windows-1252"),
+        Err(de::Error::NonAscii { key: None }));
+}
+
+#[test]
+fn deserialize_detected_charset_with_separators() {
+    let config = de::Config::new()
+        .detect_charset(true)
+        .pair_separators(b";")
+        .key_value_separator(b':');
+
+    assert_eq!(
+        config.from_str::<HashMap<String, String>>(
+            "cheese:comt%E9;_charset_:windows-1252")
+            .unwrap()["cheese"],
+        "comté");
+}
+
+#[test]
+fn deserialize_charset_ignored_without_detection() {
+    assert_eq!(
+        de::Config::new()
+            .encoding(WINDOWS_1252)
+            .from_str::<HashMap<String, String>>(
+                "_charset_=shift_jis&cheese=comt%E9")
+            .unwrap()["cheese"],
+        "comté");
+}
+
+#[test]
+fn deserialize_reader_with_encoding() {
+    let pairs = vec![("name".to_owned(), "山田".to_owned())];
+
+    assert_eq!(
+        de::Config::new()
+            .encoding(WINDOWS_31J)
+            .from_reader(&b"name=%8ER%93c"[..]),
+        Ok(pairs));
+}
+
+#[test]
+fn serialize_shift_jis() {
+    assert_eq!(
+        serde_urlencoded::to_string_with_encoding(
+            &[("名前", "山田 太郎")], WINDOWS_31J),
+        Ok("%96%BC%91O=%8ER%93c+%91%BE%98Y".to_owned()));
+}
+
+#[test]
+fn serialize_writer_with_encoding() {
+    let mut body = vec![];
+    ser::Config::new()
+        .encoding(WINDOWS_1252)
+        .to_writer(&mut body, &[("cheese", "comté")])
+        .unwrap();
+
+    assert_eq!(body, b"cheese=comt%E9");
+}
+
+#[test]
+fn round_trip_windows_1252() {
+    let pairs = vec![("menu".to_owned(), "crème brûlée, 5€".to_owned())];
+    let encoded = serde_urlencoded::to_string_with_encoding(
+        &pairs, WINDOWS_1252).unwrap();
+
+    assert_eq!(
+        serde_urlencoded::from_bytes_with_encoding(
+            encoded.as_bytes(), WINDOWS_1252),
+        Ok(pairs));
+}
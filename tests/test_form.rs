@@ -0,0 +1,51 @@
+extern crate serde_urlencoded;
+
+use serde_urlencoded::Form;
+
+#[test]
+fn form_round_trip_preserves_order() {
+    let input = "b=1&a=2&b=3&c=caf%C3%A9";
+    let form: Form = serde_urlencoded::from_str(input).unwrap();
+
+    assert_eq!(
+        form.iter().collect::<Vec<_>>(),
+        vec![("b", "1"), ("a", "2"), ("b", "3"), ("c", "café")]);
+    assert_eq!(serde_urlencoded::to_string(&form), Ok(input.to_owned()));
+}
+
+#[test]
+fn form_insert_replaces_all_values() {
+    let mut form: Form =
+        vec![("tag", "a"), ("page", "1"), ("tag", "b")].into_iter().collect();
+    form.insert("tag", "c");
+    form.insert("sort", "asc");
+
+    assert_eq!(
+        form.iter().collect::<Vec<_>>(),
+        vec![("tag", "c"), ("page", "1"), ("sort", "asc")]);
+}
+
+#[test]
+fn form_remove() {
+    let mut form: Form =
+        vec![("tag", "a"), ("page", "1"), ("tag", "b")].into_iter().collect();
+
+    assert_eq!(form.remove("tag"), vec!["a".to_owned(), "b".to_owned()]);
+    assert_eq!(form.remove("tag"), Vec::<String>::new());
+    assert_eq!(form.len(), 1);
+}
+
+#[test]
+fn form_get_as() {
+    let form: Form = serde_urlencoded::from_str("id=1&id=2&page=x").unwrap();
+
+    assert_eq!(form.get_as::<Vec<u32>>("id"), Ok(vec![1, 2]));
+    assert_eq!(form.get_as::<u32>("id"), Ok(1));
+    assert_eq!(form.get_as::<Option<u32>>("id"), Ok(Some(1)));
+    assert_eq!(form.get_as::<Option<u32>>("limit"), Ok(None));
+    assert!(form.get_as::<u32>("limit").is_err());
+
+    let err = form.get_as::<u32>("page").unwrap_err();
+    assert_eq!(err.key(), Some("page"));
+    assert_eq!(err.value(), Some("x"));
+}
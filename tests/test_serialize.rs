@@ -1,5 +1,67 @@
+extern crate serde;
 extern crate serde_urlencoded;
 
+use serde::{Serialize, Serializer};
+use serde_urlencoded::ser::{ArrayStyle, Config, EncodeSet, Error, Kind};
+use serde_urlencoded::ser::{Nesting, Position};
+use std::collections::BTreeMap;
+use std::io;
+
+struct Address {
+    city: &'static str,
+    zip: Option<u32>,
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: &mut S)
+                                -> Result<(), S::Error> {
+        let mut state = serializer.serialize_struct("Address", 2)?;
+        serializer.serialize_struct_elt(&mut state, "city", self.city)?;
+        serializer.serialize_struct_elt(&mut state, "zip", self.zip)?;
+        serializer.serialize_struct_end(state)
+    }
+}
+
+struct User {
+    name: &'static str,
+    address: Address,
+}
+
+impl Serialize for User {
+    fn serialize<S: Serializer>(&self, serializer: &mut S)
+                                -> Result<(), S::Error> {
+        let mut state = serializer.serialize_struct("User", 2)?;
+        serializer.serialize_struct_elt(&mut state, "name", self.name)?;
+        serializer.serialize_struct_elt(&mut state, "address", &self.address)?;
+        serializer.serialize_struct_end(state)
+    }
+}
+
+enum Payment {
+    Cash,
+    Card { number: &'static str, cvc: u32 },
+}
+
+impl Serialize for Payment {
+    fn serialize<S: Serializer>(&self, serializer: &mut S)
+                                -> Result<(), S::Error> {
+        match *self {
+            Payment::Cash => {
+                serializer.serialize_unit_variant("Payment", 0, "cash")
+            },
+            Payment::Card { number, cvc } => {
+                let mut state = serializer.serialize_struct_variant(
+                    "Payment", 1, "card", 2)?;
+                serializer.serialize_struct_variant_elt(
+                    &mut state, "number", number)?;
+                serializer.serialize_struct_variant_elt(
+                    &mut state, "cvc", cvc)?;
+                serializer.serialize_struct_variant_end(state)
+            },
+        }
+    }
+}
+
 #[test]
 fn serialize_option_map_int() {
     let params = &[
@@ -49,3 +111,455 @@ fn serialize_map_bool() {
         serde_urlencoded::to_string(params),
         Ok("one=true&two=false".to_owned()));
 }
+
+#[test]
+fn serialize_map_seq() {
+    let params = &[
+        ("ids", vec![1, 2, 3]),
+        ("empty", vec![]),
+        ("tag", vec![4]),
+    ];
+
+    assert_eq!(
+        serde_urlencoded::to_string(params),
+        Ok("ids=1&ids=2&ids=3&tag=4".to_owned()));
+}
+
+#[test]
+fn serialize_map_tuple() {
+    let params = &[
+        ("point", (1, "two")),
+    ];
+
+    assert_eq!(
+        serde_urlencoded::to_string(params),
+        Ok("point=1&point=two".to_owned()));
+}
+
+#[test]
+fn serialize_map_seq_brackets() {
+    let params = &[("ids", vec![1, 2])];
+
+    assert_eq!(
+        Config::new().array_style(ArrayStyle::Brackets).to_string(params),
+        Ok("ids%5B%5D=1&ids%5B%5D=2".to_owned()));
+}
+
+#[test]
+fn serialize_map_seq_indices() {
+    let params = &[("ids", vec![1, 2])];
+
+    assert_eq!(
+        Config::new().array_style(ArrayStyle::Indices).to_string(params),
+        Ok("ids%5B0%5D=1&ids%5B1%5D=2".to_owned()));
+}
+
+#[test]
+fn serialize_map_seq_comma() {
+    let params = &[
+        ("ids", vec![Some(1), None, Some(3)]),
+        ("empty", vec![]),
+    ];
+
+    assert_eq!(
+        Config::new().array_style(ArrayStyle::Comma).to_string(params),
+        Ok("ids=1,3".to_owned()));
+}
+
+#[test]
+fn serialize_seq_comma_escapes_elements() {
+    let params = &[("tags", vec!["a,b", "c d"]), ("one", vec!["e,f"])];
+
+    assert_eq!(
+        Config::new().array_style(ArrayStyle::Comma).to_string(params),
+        Ok("tags=a%2Cb,c+d&one=e%2Cf".to_owned()));
+    assert_eq!(
+        Config::new()
+            .array_style(ArrayStyle::Comma)
+            .encode_set(EncodeSet::Custom(","))
+            .to_string(params),
+        Ok("tags=a%2Cb,c%20d&one=e%2Cf".to_owned()));
+}
+
+#[test]
+fn serialize_seq_comma_pairs_error() {
+    let params = &[("tags", vec!["a,b", "c"])];
+    let err = Config::new()
+        .array_style(ArrayStyle::Comma)
+        .to_pairs(params)
+        .unwrap_err();
+
+    assert_eq!(
+        err,
+        Error::InvalidValue("`a,b` contains a comma, at `tags`".into()));
+}
+
+#[test]
+fn serialize_seq_of_maps_unsupported() {
+    let mut address = BTreeMap::new();
+    address.insert("city", "Paris");
+    let params = &[("a", vec![address.clone(), address])];
+
+    for &(style, path) in &[
+        (ArrayStyle::Comma, "a"),
+        (ArrayStyle::Repeat, "a"),
+        (ArrayStyle::Brackets, "a[]"),
+    ] {
+        let config =
+            Config::new().nesting(Nesting::Brackets).array_style(style);
+
+        assert_eq!(
+            config.to_pairs(params),
+            Err(Error::Unsupported {
+                position: Position::Value,
+                kind: Kind::Map,
+                path: Some(path.to_owned()),
+            }));
+    }
+    assert_eq!(
+        Config::new()
+            .nesting(Nesting::Brackets)
+            .array_style(ArrayStyle::Indices)
+            .to_string(params),
+        Ok("a%5B0%5D%5Bcity%5D=Paris&a%5B1%5D%5Bcity%5D=Paris".to_owned()));
+}
+
+#[test]
+fn serialize_nested_struct_brackets() {
+    let params = &[("user", User {
+        name: "Ada",
+        address: Address { city: "London", zip: None },
+    })];
+
+    assert_eq!(
+        Config::new().nesting(Nesting::Brackets).to_string(params),
+        Ok("user%5Bname%5D=Ada&user%5Baddress%5D%5Bcity%5D=London"
+            .to_owned()));
+}
+
+#[test]
+fn serialize_struct_with_nested_struct() {
+    let user = User {
+        name: "Ada",
+        address: Address { city: "London", zip: Some(12) },
+    };
+
+    assert!(serde_urlencoded::to_string(&user).is_err());
+    assert_eq!(
+        Config::new().nesting(Nesting::Brackets).to_string(&user),
+        Ok("name=Ada&address%5Bcity%5D=London&address%5Bzip%5D=12"
+            .to_owned()));
+}
+
+#[test]
+fn serialize_nested_struct_dots() {
+    let params = &[("filter", Address { city: "Oslo", zip: Some(7) })];
+
+    assert_eq!(
+        Config::new().nesting(Nesting::Dots).to_string(params),
+        Ok("filter.city=Oslo&filter.zip=7".to_owned()));
+}
+
+#[test]
+fn serialize_unit_variant_values() {
+    let params = &[("payment", Payment::Cash)];
+
+    assert_eq!(
+        serde_urlencoded::to_string(params),
+        Ok("payment=cash".to_owned()));
+}
+
+#[test]
+fn serialize_tagged_unit_variant() {
+    assert_eq!(
+        serde_urlencoded::to_string(&Payment::Cash),
+        Ok("type=cash".to_owned()));
+}
+
+#[test]
+fn serialize_tagged_struct_variant() {
+    let payment = Payment::Card { number: "4242", cvc: 123 };
+
+    assert_eq!(
+        serde_urlencoded::to_string(&payment),
+        Ok("type=card&number=4242&cvc=123".to_owned()));
+}
+
+#[test]
+fn serialize_tagged_newtype_variant() {
+    let result: Result<_, ()> = Ok(&[("number", "4242")]);
+
+    assert_eq!(
+        Config::new().tag("kind").to_string(&result),
+        Ok("kind=Ok&number=4242".to_owned()));
+}
+
+#[test]
+fn serialize_tagged_newtype_variant_unsupported() {
+    let mut body = vec![];
+    let err = serde_urlencoded::to_writer(&mut body, &Ok::<u32, u32>(3))
+        .unwrap_err();
+
+    assert_eq!(err.kind(), Some(Kind::Int));
+    assert_eq!(body, b"");
+}
+
+#[test]
+fn serialize_unsupported_value_path() {
+    let user = User {
+        name: "Ada",
+        address: Address { city: "London", zip: None },
+    };
+    let err = serde_urlencoded::to_string(&user).unwrap_err();
+
+    assert_eq!(err.path(), Some("address"));
+    assert_eq!(err.kind(), Some(Kind::Struct));
+    assert_eq!(err.to_string(), "unsupported struct value at `address`");
+}
+
+#[test]
+fn serialize_unsupported_nested_value_path() {
+    let mut filters = BTreeMap::new();
+    filters.insert("date_range", ());
+    let params = &[("filters", filters)];
+    let err = Config::new()
+        .nesting(Nesting::Dots)
+        .to_string(params)
+        .unwrap_err();
+
+    assert_eq!(
+        err,
+        Error::Unsupported {
+            position: Position::Value,
+            kind: Kind::Unit,
+            path: Some("filters.date_range".to_owned()),
+        });
+}
+
+#[test]
+fn serialize_unsupported_top_level() {
+    let err = serde_urlencoded::to_string(&42).unwrap_err();
+
+    assert_eq!(err.path(), None);
+    assert_eq!(err.kind(), Some(Kind::Int));
+}
+
+#[test]
+fn serialize_to_writer() {
+    let mut output = vec![];
+    serde_urlencoded::to_writer(&mut output, &[("city", "Zürich")]).unwrap();
+
+    assert_eq!(output, b"city=Z%C3%BCrich");
+}
+
+#[test]
+fn serialize_to_fmt_writer() {
+    let mut output = "?".to_owned();
+    serde_urlencoded::to_fmt_writer(&mut output, &[("a", 1), ("b", 2)])
+        .unwrap();
+
+    assert_eq!(output, "?a=1&b=2");
+}
+
+struct FullWriter;
+
+impl io::Write for FullWriter {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::WriteZero, "full"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn serialize_to_writer_error() {
+    let err = serde_urlencoded::to_writer(FullWriter, &[("a", 1)])
+        .unwrap_err();
+
+    match err {
+        Error::Io(err) => assert_eq!(err.kind(), io::ErrorKind::WriteZero),
+        err => panic!("unexpected error: {}", err),
+    }
+}
+
+/// Records every call to `write` and `flush`.
+#[derive(Default)]
+struct RecordingWriter {
+    writes: Vec<Vec<u8>>,
+    flushed: bool,
+}
+
+impl io::Write for RecordingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes.push(buf.to_vec());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushed = true;
+        Ok(())
+    }
+}
+
+#[test]
+fn serialize_to_writer_one_write_per_pair() {
+    let mut writer = RecordingWriter::default();
+    serde_urlencoded::to_writer(&mut writer, &[("a", "x y"), ("b", "é")])
+        .unwrap();
+
+    assert_eq!(writer.writes, vec![b"a=x+y".to_vec(), b"&b=%C3%A9".to_vec()]);
+    assert!(writer.flushed);
+}
+
+#[test]
+fn serialize_to_pairs() {
+    let user = User {
+        name: "Ada Lovelace",
+        address: Address { city: "London", zip: Some(12) },
+    };
+
+    assert_eq!(
+        Config::new().nesting(Nesting::Brackets).to_pairs(&user),
+        Ok(vec![
+            ("name".to_owned(), "Ada Lovelace".to_owned()),
+            ("address[city]".to_owned(), "London".to_owned()),
+            ("address[zip]".to_owned(), "12".to_owned()),
+        ]));
+}
+
+#[test]
+fn serialize_to_pairs_into() {
+    let mut pairs = vec![("token".to_owned(), "abc".to_owned())];
+    Config::new()
+        .array_style(ArrayStyle::Brackets)
+        .to_pairs_into(&mut pairs, &[("ids", vec![1, 2])])
+        .unwrap();
+
+    assert_eq!(
+        pairs,
+        vec![
+            ("token".to_owned(), "abc".to_owned()),
+            ("ids[]".to_owned(), "1".to_owned()),
+            ("ids[]".to_owned(), "2".to_owned()),
+        ]);
+}
+
+#[test]
+fn serialize_form_encode_set() {
+    assert_eq!(
+        serde_urlencoded::to_string(&[("q", "a b~*'()!")]),
+        Ok("q=a+b%7E*%27%28%29%21".to_owned()));
+}
+
+#[test]
+fn serialize_rfc3986_encode_set() {
+    assert_eq!(
+        Config::new()
+            .encode_set(EncodeSet::Rfc3986)
+            .to_string(&[("file name", "a b~*+é")]),
+        Ok("file%20name=a%20b~%2A%2B%C3%A9".to_owned()));
+}
+
+#[test]
+fn serialize_rfc3986_encode_set_nested_keys() {
+    let user = User {
+        name: "Ada Lovelace",
+        address: Address { city: "London", zip: None },
+    };
+
+    assert_eq!(
+        Config::new()
+            .nesting(Nesting::Brackets)
+            .encode_set(EncodeSet::Rfc3986)
+            .to_string(&user),
+        Ok("name=Ada%20Lovelace&address%5Bcity%5D=London".to_owned()));
+}
+
+#[test]
+fn serialize_custom_encode_set() {
+    assert_eq!(
+        Config::new()
+            .encode_set(EncodeSet::Custom("[]/ é"))
+            .to_string(&[("ids[]", "a/b c"), ("name", "é")]),
+        Ok("ids[]=a/b c&name=%C3%A9".to_owned()));
+}
+
+#[test]
+fn serialize_rfc3986_encode_set_to_writer() {
+    let mut body = vec![];
+    Config::new()
+        .encode_set(EncodeSet::Rfc3986)
+        .to_writer(&mut body, &[("q", "rust serde")])
+        .unwrap();
+
+    assert_eq!(body, b"q=rust%20serde");
+}
+
+#[test]
+fn serialize_semicolon_separator() {
+    assert_eq!(
+        Config::new()
+            .pair_separator(b';')
+            .to_string(&[("a", "1;2"), ("b", "3&4")]),
+        Ok("a=1%3B2;b=3%264".to_owned()));
+}
+
+#[test]
+fn serialize_separators_always_escaped() {
+    assert_eq!(
+        Config::new()
+            .encode_set(EncodeSet::Rfc3986)
+            .pair_separator(b'~')
+            .key_value_separator(b'.')
+            .to_string(&[("v1.2", "x~y"), ("z", "")]),
+        Ok("v1%2E2.x%7Ey~z.".to_owned()));
+}
+
+#[test]
+#[should_panic(expected = "pair separator must be ASCII punctuation")]
+fn serialize_non_ascii_separator() {
+    let _ = Config::new().pair_separator(0xA7);
+}
+
+#[test]
+#[should_panic(expected = "pair separator must be ASCII punctuation")]
+fn serialize_percent_separator() {
+    let _ = Config::new().pair_separator(b'%');
+}
+
+#[test]
+#[should_panic(expected = "key/value separator must be ASCII punctuation")]
+fn serialize_alphanumeric_separator() {
+    let _ = Config::new().key_value_separator(b'A');
+}
+
+#[test]
+fn serialize_swapped_separators() {
+    assert_eq!(
+        Config::new()
+            .pair_separator(b'=')
+            .key_value_separator(b'&')
+            .to_string(&[("a", "1"), ("b", "2")]),
+        Ok("a&1=b&2".to_owned()));
+}
+
+#[test]
+fn serialize_conflicting_separators() {
+    assert_eq!(
+        Config::new().key_value_separator(b'&').to_string(&[("a", "1")]),
+        Err(Error::Custom("pair and key/value separators must differ".into())));
+}
+
+#[test]
+#[allow(deprecated)]
+fn serialize_error_description() {
+    use serde_urlencoded::ser::Error;
+    use std::error::Error as StdError;
+
+    let err = Error::InvalidValue("not a string".into());
+
+    assert_eq!(err.description(), "not a string");
+    assert!(err.cause().is_none());
+}
@@ -0,0 +1,48 @@
+extern crate serde_urlencoded;
+extern crate url;
+
+use serde_urlencoded::UrlExt;
+use std::collections::HashMap;
+use url::Url;
+
+#[test]
+fn append_query_pairs() {
+    let mut url = Url::parse("https://example.com/items?sort=asc").unwrap();
+    url.append_query_pairs(&[("ids", vec![1, 2])]).unwrap();
+
+    assert_eq!(url.query(), Some("sort=asc&ids=1&ids=2"));
+}
+
+#[test]
+fn append_query_pairs_error_leaves_url_unchanged() {
+    let mut url = Url::parse("https://example.com/items?sort=asc").unwrap();
+
+    assert!(url.append_query_pairs(&[("a", 1)]).is_ok());
+    assert!(url.append_query_pairs(&42).is_err());
+    assert_eq!(url.query(), Some("sort=asc&a=1"));
+}
+
+#[test]
+fn set_query_from_empty() {
+    let mut url = Url::parse("https://example.com/items?sort=asc").unwrap();
+    url.set_query_from(&Vec::<(String, String)>::new()).unwrap();
+
+    assert_eq!(url.as_str(), "https://example.com/items");
+}
+
+#[test]
+fn query_as() {
+    let url = Url::parse("https://example.com/items?page=2&limit=50").unwrap();
+    let mut result = HashMap::new();
+    result.insert("page".to_owned(), 2);
+    result.insert("limit".to_owned(), 50);
+
+    assert_eq!(url.query_as(), Ok(result));
+}
+
+#[test]
+fn query_as_without_query() {
+    let url = Url::parse("https://example.com/items").unwrap();
+
+    assert_eq!(url.query_as(), Ok(HashMap::<String, u32>::new()));
+}